@@ -0,0 +1,73 @@
+// src/cli/ask.rs
+use std::io::{self, Write};
+
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::cli::FilterArgs;
+use crate::llama::answer::RagAnswer;
+use crate::llama::LlamaService;
+use crate::prelude::*;
+use crate::rag::vectors::VectorDB;
+
+/// Answers a question from the notes. The answer is streamed to stdout followed by the
+/// cited sources, or printed as a JSON object once complete.
+pub async fn ask(
+    question: &str,
+    filters: &FilterArgs,
+    json: bool,
+    llama_service: &LlamaService,
+    vector_db: &VectorDB,
+) -> Result<()> {
+    let (question, filter) = filters.parse_query(question);
+    if question.is_empty() {
+        return Err(AppError::InputError(
+            "Please add a question to the filters".into(),
+        ));
+    }
+
+    let (tokens, mut token_receiver) = unbounded_channel();
+    let generate =
+        llama_service.generate_rag_completion_stream(&[], &question, vector_db, &filter, tokens);
+    let print = async {
+        // Keep receiving in JSON mode too, as generation stops once the receiver is dropped
+        let mut stdout = io::stdout();
+        while let Some(token) = token_receiver.recv().await {
+            if !json {
+                write!(stdout, "{}", token)?;
+                stdout.flush()?;
+            }
+        }
+        io::Result::Ok(())
+    };
+    let (answer, printed) = tokio::join!(generate, print);
+    let answer = answer?;
+    printed?;
+
+    let mut stdout = io::stdout().lock();
+    if json {
+        serde_json::to_writer_pretty(&mut stdout, &answer)?;
+        writeln!(stdout)?;
+    } else {
+        writeln!(stdout)?;
+        print_sources(&mut stdout, &answer)?;
+    }
+    Ok(())
+}
+
+/// Prints the sources the answer cites, after an empty line.
+pub fn print_sources(out: &mut impl Write, answer: &RagAnswer) -> io::Result<()> {
+    let sources = answer.cited_sources();
+    if sources.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "\nSources:")?;
+    for (number, source) in sources {
+        writeln!(
+            out,
+            "  [{}] {} - {} ({:.2})",
+            number, source.section, source.file_path, source.score
+        )?;
+    }
+    Ok(())
+}
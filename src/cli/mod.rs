@@ -0,0 +1,89 @@
+// src/cli/mod.rs
+use chrono::NaiveDate;
+use clap::Args;
+use serde::Deserialize;
+
+use crate::rag::filters::{parse_date, parse_query, SearchFilter};
+
+pub mod ask;
+pub mod search;
+
+/// Flags restricting a query to a subset of the notes, in addition to inline filters
+#[derive(Debug, Default, Args, Deserialize)]
+#[serde(default)]
+pub struct FilterArgs {
+    /// Only notes with this tag; repeat to require several tags
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Only notes in this folder, relative to the notes directory
+    #[arg(long, value_name = "FOLDER", value_parser = folder_arg)]
+    pub path: Option<String>,
+
+    /// Only notes modified on or after this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = date_arg)]
+    pub after: Option<NaiveDate>,
+
+    /// Only notes modified on or before this day (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = date_arg)]
+    pub before: Option<NaiveDate>,
+}
+
+impl FilterArgs {
+    /// Splits inline filters from the query and adds the flags to them.
+    pub fn parse_query(&self, query: &str) -> (String, SearchFilter) {
+        let (query, mut filter) = parse_query(query);
+
+        filter.tags.extend(
+            self.tags
+                .iter()
+                .map(|tag| tag.trim_start_matches('#').to_string()),
+        );
+        if let Some(path) = &self.path {
+            filter.set_folder(path);
+        }
+        if let Some(after) = self.after {
+            filter.set_modified_after(after);
+        }
+        if let Some(before) = self.before {
+            filter.set_modified_before(before);
+        }
+
+        (query, filter)
+    }
+}
+
+fn folder_arg(value: &str) -> std::result::Result<String, String> {
+    if value.trim_matches('/').is_empty() {
+        Err("expected a folder such as projects/raggy".to_string())
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+fn date_arg(value: &str) -> std::result::Result<NaiveDate, String> {
+    parse_date(value).ok_or_else(|| format!("expected a date like 2024-01-31, got '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_are_added_to_inline_filters() {
+        let filters = FilterArgs {
+            tags: vec!["#ops".to_string()],
+            path: Some("projects/".to_string()),
+            after: NaiveDate::from_ymd_opt(2024, 1, 31),
+            before: None,
+        };
+
+        let (query, filter) = filters.parse_query("tag:work how do I reset the VPN?");
+        assert_eq!(query, "how do I reset the VPN?");
+        assert_eq!(filter.tags, vec!["work", "ops"]);
+        assert_eq!(
+            filter.to_string(),
+            "path:projects/ tag:work tag:ops after:2024-01-31"
+        );
+    }
+}
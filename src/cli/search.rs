@@ -0,0 +1,114 @@
+// src/cli/search.rs
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+
+use crate::cli::FilterArgs;
+use crate::llama::answer::NoteSource;
+use crate::llama::LlamaService;
+use crate::prelude::*;
+use crate::rag::vectors::VectorDB;
+
+/// How search hits are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Ranked hits with their snippets, for reading
+    #[default]
+    Human,
+    /// One JSON object per hit
+    Json,
+    /// Score, title, path and snippet separated by tabs, one hit per line
+    Tsv,
+}
+
+/// Searches the notes and prints the hits, best first. With `min_score`, hits whose
+/// embedding similarity to the query is lower are left out.
+pub async fn search(
+    query: &str,
+    filters: &FilterArgs,
+    limit: u64,
+    min_score: Option<f32>,
+    format: OutputFormat,
+    llama_service: &LlamaService,
+    vector_db: &VectorDB,
+) -> Result<()> {
+    let (query, filter) = filters.parse_query(query);
+    if query.is_empty() {
+        return Err(AppError::InputError(
+            "Please add search terms to the filters".into(),
+        ));
+    }
+
+    let embedding = llama_service.get_embedding(&query).await?;
+    let hits: Vec<NoteSource> = vector_db
+        .search_notes(&query, embedding, limit, &filter)
+        .await?
+        .iter()
+        .filter(|note| min_score.is_none_or(|min_score| note.is_similar(min_score)))
+        .map(NoteSource::from_scored_note)
+        .collect();
+
+    let mut stdout = io::stdout().lock();
+    for (rank, hit) in hits.iter().enumerate() {
+        writeln!(stdout, "{}", format_hit(format, rank + 1, hit)?)?;
+    }
+    if hits.is_empty() && format == OutputFormat::Human {
+        writeln!(stdout, "No matching notes found.")?;
+    }
+    Ok(())
+}
+
+fn format_hit(format: OutputFormat, rank: usize, hit: &NoteSource) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Human => format!(
+            "{}. {} ({:.2})\n   {}\n   {}",
+            rank, hit.section, hit.score, hit.file_path, hit.snippet
+        ),
+        OutputFormat::Json => serde_json::to_string(hit)?,
+        OutputFormat::Tsv => [
+            format!("{:.4}", hit.score),
+            tsv_field(&hit.title),
+            tsv_field(&hit.file_path),
+            tsv_field(&hit.snippet),
+        ]
+        .join("\t"),
+    })
+}
+
+/// Replaces the tabs and line breaks that would split a TSV record.
+fn tsv_field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_hit() {
+        let hit = NoteSource {
+            id: "1".to_string(),
+            title: "VPN\tsetup".to_string(),
+            section: "VPN setup > Reset".to_string(),
+            file_path: "/notes/work/vpn.md".to_string(),
+            score: 0.8765,
+            similarity: Some(0.61),
+            snippet: "Restart the gateway".to_string(),
+            byte_start: 0,
+            byte_end: 19,
+        };
+
+        assert_eq!(
+            format_hit(OutputFormat::Tsv, 1, &hit).unwrap(),
+            "0.8765\tVPN setup\t/notes/work/vpn.md\tRestart the gateway"
+        );
+        assert_eq!(
+            format_hit(OutputFormat::Human, 2, &hit).unwrap(),
+            "2. VPN setup > Reset (0.88)\n   /notes/work/vpn.md\n   Restart the gateway"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&format_hit(OutputFormat::Json, 1, &hit).unwrap()).unwrap();
+        assert_eq!(json["file_path"], "/notes/work/vpn.md");
+    }
+}
@@ -3,10 +3,148 @@ use std::{error::Error, fs, path::PathBuf};
 use xdg::BaseDirectories;
 
 const DEFAULT_PREFIX: &str = "raggy_notes";
+const DEFAULT_CHUNK_SIZE: usize = 1500;
+const DEFAULT_CHUNK_OVERLAP: usize = 200;
+const DEFAULT_KEYWORD_WEIGHT: f32 = 0.3;
+const LOCAL_STORE_FILE_NAME: &str = "vectors.json";
+const DEFAULT_COMPLETION_MODEL: &str = "gemma3:4b";
+const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+const DEFAULT_CONTEXT_TOKENS: usize = 2048;
+const DEFAULT_QDRANT_URL: &str = "http://localhost:6334";
+const DEFAULT_TOP_K: u64 = 5;
+const DEFAULT_COLLECTION_NAME: &str = "private_notes";
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// Placeholder of the prompt template replaced by the retrieved notes
+pub const PROMPT_NOTES_PLACEHOLDER: &str = "{notes}";
+const DEFAULT_PROMPT_TEMPLATE: &str = "You are a helpful AI assistant with access to the user's \
+notes. Answer the user's questions using the provided notes when relevant. If the notes don't \
+contain relevant information, just answer based on your knowledge.\n\n{notes}";
+
+/// Backend storing the note vectors
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VectorStoreKind {
+    /// A Qdrant server
+    #[default]
+    Qdrant,
+    /// An embedded index persisted in the XDG data directory
+    Local,
+}
+
+impl VectorStoreKind {
+    /// Name of the backend shown to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            VectorStoreKind::Qdrant => "Qdrant",
+            VectorStoreKind::Local => "Local index",
+        }
+    }
+}
+
+/// Service generating completions and embeddings
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmProviderKind {
+    /// An Ollama server, using its native API
+    #[default]
+    Ollama,
+    /// Any server implementing the OpenAI HTTP API (llama.cpp server, vLLM, LM Studio)
+    OpenAi,
+}
+
+impl LlmProviderKind {
+    /// Name of the provider shown to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            LlmProviderKind::Ollama => "Ollama",
+            LlmProviderKind::OpenAi => "LLM server",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AppConfiguration {
     pub scan_path: String,
+    /// Maximum size of an embedded note chunk, in bytes
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+    /// Number of bytes shared between consecutive chunks of a section
+    #[serde(default = "default_chunk_overlap")]
+    pub chunk_overlap: usize,
+    /// Share of keyword matches in hybrid search, from 0 (vectors only) to 1 (keywords only)
+    #[serde(default = "default_keyword_weight")]
+    pub keyword_weight: f32,
+    #[serde(default)]
+    pub vector_store: VectorStoreKind,
+    /// gRPC address of the Qdrant server
+    #[serde(default = "default_qdrant_url")]
+    pub qdrant_url: String,
+    /// Qdrant collection holding the note vectors
+    #[serde(default = "default_collection_name")]
+    pub collection_name: String,
+    #[serde(default)]
+    pub llm_provider: LlmProviderKind,
+    /// Base URL of the LLM provider; defaults to its usual local address
+    #[serde(default)]
+    pub llm_base_url: Option<String>,
+    /// Name of the environment variable holding the provider's API key, if it needs one
+    #[serde(default)]
+    pub llm_api_key_env: Option<String>,
+    /// Model used to answer questions
+    #[serde(default = "default_completion_model")]
+    pub completion_model: String,
+    /// Model used to embed notes and queries
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+    /// Maximum number of tokens of note passages put into a question's prompt
+    #[serde(default = "default_context_tokens")]
+    pub context_tokens: usize,
+    /// Number of note chunks retrieved to answer a question
+    #[serde(default = "default_top_k")]
+    pub top_k: u64,
+    /// System prompt of the chat; `{notes}` is replaced by the retrieved notes
+    #[serde(default = "default_prompt_template")]
+    pub prompt_template: String,
+}
+
+fn default_chunk_size() -> usize {
+    DEFAULT_CHUNK_SIZE
+}
+
+fn default_chunk_overlap() -> usize {
+    DEFAULT_CHUNK_OVERLAP
+}
+
+fn default_keyword_weight() -> f32 {
+    DEFAULT_KEYWORD_WEIGHT
+}
+
+fn default_completion_model() -> String {
+    DEFAULT_COMPLETION_MODEL.to_string()
+}
+
+fn default_embedding_model() -> String {
+    DEFAULT_EMBEDDING_MODEL.to_string()
+}
+
+fn default_context_tokens() -> usize {
+    DEFAULT_CONTEXT_TOKENS
+}
+
+fn default_qdrant_url() -> String {
+    DEFAULT_QDRANT_URL.to_string()
+}
+
+fn default_collection_name() -> String {
+    DEFAULT_COLLECTION_NAME.to_string()
+}
+
+fn default_top_k() -> u64 {
+    DEFAULT_TOP_K
+}
+
+fn default_prompt_template() -> String {
+    DEFAULT_PROMPT_TEMPLATE.to_string()
 }
 
 impl Default for AppConfiguration {
@@ -19,17 +157,85 @@ impl AppConfiguration {
     pub fn new(scan_path: impl Into<String>) -> Self {
         Self {
             scan_path: scan_path.into(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+            keyword_weight: DEFAULT_KEYWORD_WEIGHT,
+            vector_store: VectorStoreKind::default(),
+            qdrant_url: default_qdrant_url(),
+            collection_name: default_collection_name(),
+            llm_provider: LlmProviderKind::default(),
+            llm_base_url: None,
+            llm_api_key_env: None,
+            completion_model: default_completion_model(),
+            embedding_model: default_embedding_model(),
+            context_tokens: DEFAULT_CONTEXT_TOKENS,
+            top_k: DEFAULT_TOP_K,
+            prompt_template: default_prompt_template(),
         }
     }
 
+    /// Loads the configuration file, with the `RAGGY_*` environment variables taking
+    /// precedence over its values. Fails if there is no configuration file.
     pub fn load() -> Result<Self, Box<dyn Error>> {
+        let config = Self::load_from_xdg(DEFAULT_PREFIX)?.ok_or("Config file not found")?;
+        Ok(config.with_overrides())
+    }
+
+    /// Like `load`, but starts from the defaults if there is no configuration file yet.
+    /// A configuration file that can't be read or parsed is still an error.
+    pub fn load_or_default() -> Result<Self, Box<dyn Error>> {
+        let config = Self::load_from_xdg(DEFAULT_PREFIX)?.unwrap_or_default();
+        Ok(config.with_overrides())
+    }
+
+    /// Loads the configuration file as it is written, without the environment
+    /// overrides, or `None` if there is none.
+    pub fn load_file() -> Result<Option<Self>, Box<dyn Error>> {
         Self::load_from_xdg(DEFAULT_PREFIX)
     }
 
+    fn with_overrides(mut self) -> Self {
+        self.apply_overrides(|variable| std::env::var(variable).ok());
+        self
+    }
+
+    /// Replaces settings by the values of the set override variables.
+    fn apply_overrides(&mut self, variable: impl Fn(&str) -> Option<String>) {
+        let set = |name: &str| variable(name).filter(|value| !value.trim().is_empty());
+
+        // RAGGY_OLLAMA_URL only points at an Ollama server, RAGGY_LLM_URL at any provider
+        if self.llm_provider == LlmProviderKind::Ollama {
+            if let Some(url) = set("RAGGY_OLLAMA_URL") {
+                self.llm_base_url = Some(url);
+            }
+        }
+        if let Some(url) = set("RAGGY_LLM_URL") {
+            self.llm_base_url = Some(url);
+        }
+        if let Some(url) = set("RAGGY_QDRANT_URL") {
+            self.qdrant_url = url;
+        }
+        if let Some(name) = set("RAGGY_COLLECTION") {
+            self.collection_name = name;
+        }
+        if let Some(model) = set("RAGGY_COMPLETION_MODEL") {
+            self.completion_model = model;
+        }
+        if let Some(model) = set("RAGGY_EMBEDDING_MODEL") {
+            self.embedding_model = model;
+        }
+    }
+
     pub fn save(&self) -> Result<PathBuf, Box<dyn Error>> {
         self.save_to_xdg(DEFAULT_PREFIX)
     }
 
+    /// Location of the index used by the `local` vector store.
+    pub fn local_store_path() -> Result<PathBuf, Box<dyn Error>> {
+        let xdg_dirs = BaseDirectories::with_prefix(DEFAULT_PREFIX)?;
+        Ok(xdg_dirs.place_data_file(LOCAL_STORE_FILE_NAME)?)
+    }
+
     fn save_to_xdg(&self, prefix: &str) -> Result<PathBuf, Box<dyn Error>> {
         let xdg_dirs = BaseDirectories::with_prefix(prefix)?;
         let config_file_path = xdg_dirs.place_config_file("config.json")?;
@@ -40,15 +246,17 @@ impl AppConfiguration {
         Ok(config_file_path)
     }
 
-    fn load_from_xdg(prefix: &str) -> Result<Self, Box<dyn Error>> {
+    /// Reads the configuration file, or returns `None` if there is none.
+    fn load_from_xdg(prefix: &str) -> Result<Option<Self>, Box<dyn Error>> {
         let xdg_dirs = BaseDirectories::with_prefix(prefix)?;
-        let config_file_path = xdg_dirs
-            .find_config_file("config.json")
-            .ok_or("Config file not found")?;
+        let Some(config_file_path) = xdg_dirs.find_config_file("config.json") else {
+            return Ok(None);
+        };
 
-        let content = fs::read_to_string(config_file_path)?;
-        let config: Self = serde_json::from_str(&content)?;
-        Ok(config)
+        let content = fs::read_to_string(&config_file_path)?;
+        let config: Self = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid {}: {}", config_file_path.display(), e))?;
+        Ok(Some(config))
     }
 }
 
@@ -69,7 +277,7 @@ mod tests {
         assert!(config_path.exists(), "Config file was not created");
 
         let loaded_config = AppConfiguration::load_from_xdg(prefix).unwrap();
-        assert_eq!(original_config, loaded_config);
+        assert_eq!(Some(original_config), loaded_config);
     }
 
     #[test]
@@ -77,7 +285,50 @@ mod tests {
         let dir = TempDir::new("tst").unwrap();
         let prefix = dir.path().to_str().unwrap();
 
-        let result = AppConfiguration::load_from_xdg(prefix);
-        assert!(result.is_err(), "Should error when config file is missing");
+        let result = AppConfiguration::load_from_xdg(prefix).unwrap();
+        assert!(
+            result.is_none(),
+            "Should find no config when the file is missing"
+        );
+    }
+
+    #[test]
+    fn test_configuration_with_missing_fields_uses_defaults() {
+        let config: AppConfiguration =
+            serde_json::from_str(r#"{"scan_path": "/tmp/scan"}"#).unwrap();
+        assert_eq!(config, AppConfiguration::new("/tmp/scan"));
+    }
+
+    #[test]
+    fn test_environment_overrides() {
+        let mut config = AppConfiguration::new("/tmp/scan");
+        config.apply_overrides(|variable| match variable {
+            "RAGGY_OLLAMA_URL" => Some("http://gpu-box:11434".to_string()),
+            "RAGGY_EMBEDDING_MODEL" => Some("mxbai-embed-large".to_string()),
+            "RAGGY_COLLECTION" => Some(String::new()),
+            _ => None,
+        });
+
+        assert_eq!(config.llm_base_url.as_deref(), Some("http://gpu-box:11434"));
+        assert_eq!(config.embedding_model, "mxbai-embed-large");
+        assert_eq!(config.collection_name, DEFAULT_COLLECTION_NAME);
+
+        let mut config = AppConfiguration::new("/tmp/scan");
+        config.llm_provider = LlmProviderKind::OpenAi;
+        config.llm_base_url = Some("http://localhost:8080".to_string());
+        config.apply_overrides(|variable| match variable {
+            "RAGGY_OLLAMA_URL" => Some("http://gpu-box:11434".to_string()),
+            _ => None,
+        });
+        assert_eq!(
+            config.llm_base_url.as_deref(),
+            Some("http://localhost:8080")
+        );
+
+        config.apply_overrides(|variable| match variable {
+            "RAGGY_LLM_URL" => Some("http://gpu-box:8080".to_string()),
+            _ => None,
+        });
+        assert_eq!(config.llm_base_url.as_deref(), Some("http://gpu-box:8080"));
     }
 }
@@ -2,8 +2,8 @@ use qdrant_client::QdrantError;
 // src/error.rs
 use thiserror::Error;
 
-
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum AppError {
     #[error("I/O error: {0}")]
     IOError(#[from] std::io::Error),
@@ -17,8 +17,11 @@ pub enum AppError {
     #[error("Ollama API error: {0}")]
     OllamaError(String),
 
+    #[error("LLM provider error: {0}")]
+    LlmError(String),
+
     #[error("Qdrant client error: {0}")]
-    QdrantError(#[from] QdrantError),
+    QdrantError(#[from] Box<QdrantError>),
 
     #[error("Vector database error: {0}")]
     VectorDBError(String),
@@ -26,6 +29,9 @@ pub enum AppError {
     #[error("TUI error: {0}")]
     TUIError(String),
 
+    #[error("Invalid input: {0}")]
+    InputError(String),
+
     #[error("Unexpected error: {0}")]
     UnexpectedError(String),
 }
@@ -43,6 +49,13 @@ impl From<&str> for AppError {
     }
 }
 
+// Qdrant errors are boxed to keep `Result<T, AppError>` small
+impl From<QdrantError> for AppError {
+    fn from(err: QdrantError) -> Self {
+        AppError::QdrantError(Box::new(err))
+    }
+}
+
 // Implement conversion from Ollama errors
 impl From<ollama_rs::error::InternalOllamaError> for AppError {
     fn from(err: ollama_rs::error::InternalOllamaError) -> Self {
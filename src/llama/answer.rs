@@ -0,0 +1,138 @@
+// src/llama/answer.rs
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::llama::context::{truncate_chars, DroppedPassage};
+use crate::rag::vectors::ScoredNote;
+
+/// Number of characters of a note chunk kept as the snippet of a source
+const SNIPPET_LENGTH: usize = 200;
+
+/// An answer generated from the user's notes.
+#[derive(Debug, Clone, Serialize)]
+pub struct RagAnswer {
+    pub answer: String,
+    /// The notes given to the model, in the order they are numbered in the prompt, so
+    /// that `[1]` in the answer refers to the first source
+    pub sources: Vec<NoteSource>,
+    /// Retrieved notes that didn't fit into the prompt
+    pub dropped: Vec<DroppedPassage>,
+}
+
+impl RagAnswer {
+    /// Returns the sources the answer cites, with their numbers, or all sources when the
+    /// answer cites none of them.
+    pub fn cited_sources(&self) -> Vec<(usize, &NoteSource)> {
+        cited_sources(&self.answer, &self.sources)
+    }
+}
+
+/// Returns the `sources` cited by `answer`, with their numbers, or all of them when it
+/// cites none. Every frontend lists the sources of an answer by this rule.
+pub fn cited_sources<'a>(answer: &str, sources: &'a [NoteSource]) -> Vec<(usize, &'a NoteSource)> {
+    let cited = cited_numbers(answer);
+    let numbered = sources
+        .iter()
+        .enumerate()
+        .map(|(i, source)| (i + 1, source));
+
+    if numbered.clone().any(|(number, _)| cited.contains(&number)) {
+        numbered
+            .filter(|(number, _)| cited.contains(number))
+            .collect()
+    } else {
+        numbered.collect()
+    }
+}
+
+/// Collects the numbers of citations like `[1]` or `[2, 3]`.
+fn cited_numbers(answer: &str) -> HashSet<usize> {
+    answer
+        .split('[')
+        .skip(1)
+        .filter_map(|rest| rest.split_once(']'))
+        .flat_map(|(citation, _)| citation.split(','))
+        .filter_map(|number| number.trim().parse().ok())
+        .collect()
+}
+
+/// A note chunk an answer was based on.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NoteSource {
+    pub id: String,
+    pub title: String,
+    /// Heading path of the chunk within the note
+    pub section: String,
+    pub file_path: String,
+    pub score: f32,
+    /// Cosine similarity to the question, if found by embedding
+    pub similarity: Option<f32>,
+    pub snippet: String,
+    /// Byte range of the chunk within the note file
+    pub byte_start: usize,
+    pub byte_end: usize,
+}
+
+impl NoteSource {
+    pub fn from_scored_note(note: &ScoredNote) -> Self {
+        Self {
+            id: note.id.clone(),
+            title: note.payload.title.clone(),
+            section: note.payload.section_label(),
+            file_path: note.payload.file_path.clone(),
+            score: note.score,
+            similarity: note.similarity,
+            snippet: snippet(&note.payload.content),
+            byte_start: note.payload.byte_start,
+            byte_end: note.payload.byte_end,
+        }
+    }
+}
+
+/// Returns the start of the text on a single line, shortened to whole characters.
+fn snippet(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate_chars(&text, SNIPPET_LENGTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_is_single_line_and_utf8_safe() {
+        assert_eq!(snippet("# Tasks\n\n- buy milk"), "# Tasks - buy milk");
+
+        let long = "ä".repeat(SNIPPET_LENGTH + 10);
+        let shortened = snippet(&long);
+        assert_eq!(shortened.chars().count(), SNIPPET_LENGTH + 3);
+        assert!(shortened.ends_with("..."));
+    }
+
+    #[test]
+    fn test_cited_sources() {
+        let source = |id: &str| NoteSource {
+            id: id.to_string(),
+            title: id.to_string(),
+            section: id.to_string(),
+            file_path: format!("/notes/{}.md", id),
+            score: 0.5,
+            similarity: None,
+            snippet: String::new(),
+            byte_start: 0,
+            byte_end: 0,
+        };
+        let mut answer = RagAnswer {
+            answer: "Restart the gateway [3] after the backup [1, 3].".to_string(),
+            sources: vec![source("a"), source("b"), source("c")],
+            dropped: Vec::new(),
+        };
+
+        let cited: Vec<_> = answer.cited_sources().iter().map(|(n, _)| *n).collect();
+        assert_eq!(cited, [1, 3]);
+
+        answer.answer = "I couldn't find it in [the notes].".to_string();
+        assert_eq!(answer.cited_sources().len(), 3);
+    }
+}
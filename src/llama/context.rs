@@ -0,0 +1,229 @@
+// src/llama/context.rs
+use serde::Serialize;
+
+use crate::rag::vectors::ScoredNote;
+
+/// Rough number of characters per token, good enough to budget English and Polish text
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Passages that would have to be cut below this many tokens are dropped instead
+const MIN_PASSAGE_TOKENS: usize = 32;
+
+/// The notes context of a RAG prompt.
+#[derive(Debug)]
+pub struct NoteContext {
+    /// Numbered passages, ready to be put into the prompt
+    pub text: String,
+    /// The notes in the context, in the order they are numbered
+    pub notes: Vec<ScoredNote>,
+    /// Notes that were left out, best ranked first
+    pub dropped: Vec<DroppedPassage>,
+}
+
+/// A retrieved note chunk that didn't make it into the context.
+#[derive(Debug, Clone, Serialize)]
+pub struct DroppedPassage {
+    pub section: String,
+    pub file_path: String,
+    pub reason: DropReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    /// The same text is already part of a better ranked passage
+    Duplicate,
+    /// The token budget was used up by better ranked passages
+    OverBudget,
+}
+
+/// Estimates the number of tokens the text takes up in the model's context.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Shortens the text to at most `max_chars` characters, marking the cut with "...".
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Shortens the text to about `max_tokens` tokens, preferring to cut after a sentence
+/// and otherwise between words. Returns the text unchanged if it fits.
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    let max_chars = max_tokens * CHARS_PER_TOKEN;
+    let Some((limit, _)) = text.char_indices().nth(max_chars) else {
+        return text.to_string();
+    };
+    let prefix = &text[..limit];
+
+    // Only cut at a sentence end if that keeps most of the allowed text
+    let sentence_end = prefix
+        .char_indices()
+        .rev()
+        .filter(|(i, c)| {
+            matches!(c, '.' | '!' | '?' | '\n')
+                && prefix[i + c.len_utf8()..]
+                    .chars()
+                    .next()
+                    .is_none_or(char::is_whitespace)
+        })
+        .map(|(i, c)| i + c.len_utf8())
+        .next()
+        .filter(|end| *end >= limit / 2);
+
+    let end = sentence_end
+        .or_else(|| prefix.rfind(char::is_whitespace))
+        .unwrap_or(limit);
+
+    format!("{}...", prefix[..end].trim_end())
+}
+
+/// Collapses whitespace and case, so that passages differing only in formatting compare
+/// equal.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Fills the context with the ranked notes until `token_budget` is used up.
+///
+/// Passages whose text is contained in a better ranked passage are skipped, and the last
+/// passage that fits is shortened to the remaining budget.
+pub fn build_context(ranked_notes: Vec<ScoredNote>, token_budget: usize) -> NoteContext {
+    let mut context = NoteContext {
+        text: String::new(),
+        notes: Vec::new(),
+        dropped: Vec::new(),
+    };
+    let mut included_texts: Vec<String> = Vec::new();
+    let mut remaining = token_budget;
+
+    for note in ranked_notes {
+        let dropped = |reason| DroppedPassage {
+            section: note.payload.section_label(),
+            file_path: note.payload.file_path.clone(),
+            reason,
+        };
+
+        let normalized = normalize(&note.payload.content);
+        if included_texts
+            .iter()
+            .any(|included| included.contains(&normalized))
+        {
+            context.dropped.push(dropped(DropReason::Duplicate));
+            continue;
+        }
+
+        let header = format!(
+            "[{}] {} ({})\n",
+            context.notes.len() + 1,
+            note.payload.section_label(),
+            note.payload.file_path
+        );
+        let available = remaining.saturating_sub(estimate_tokens(&header));
+        // Leave room for the "..." of a cut passage
+        let content = note.payload.content.trim();
+        let max_tokens = available.saturating_sub(1);
+        if estimate_tokens(content) > max_tokens && max_tokens < MIN_PASSAGE_TOKENS {
+            context.dropped.push(dropped(DropReason::OverBudget));
+            continue;
+        }
+
+        let passage = truncate_to_tokens(content, max_tokens) + "\n\n";
+        remaining = available.saturating_sub(estimate_tokens(&passage));
+
+        context.text.push_str(&header);
+        context.text.push_str(&passage);
+        included_texts.push(normalized);
+        context.notes.push(note);
+    }
+
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rag::vectors::NotePayload;
+
+    fn scored_note(file_path: &str, content: &str) -> ScoredNote {
+        let payload: NotePayload = serde_json::from_value(serde_json::json!({
+            "title": "Note",
+            "content": content,
+            "file_path": file_path,
+            "content_hash": "hash",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        ScoredNote {
+            id: file_path.to_string(),
+            score: 1.0,
+            similarity: Some(1.0),
+            payload,
+        }
+    }
+
+    #[test]
+    fn test_truncate_on_char_boundaries() {
+        let polish = "Zażółć gęślą jaźń. ".repeat(20);
+        let truncated = truncate_to_tokens(&polish, 10);
+        assert!(truncated.ends_with("jaźń...."));
+        assert!(truncated.chars().count() <= 10 * CHARS_PER_TOKEN + 3);
+
+        assert_eq!(truncate_chars("żółw", 2), "żó...");
+        assert_eq!(truncate_chars("żółw", 4), "żółw");
+    }
+
+    #[test]
+    fn test_truncate_between_words_without_sentences() {
+        let text = "word ".repeat(100);
+        assert_eq!(truncate_to_tokens(&text, 3), "word word...");
+    }
+
+    #[test]
+    fn test_build_context_skips_duplicates_and_respects_budget() {
+        let long = "A sentence about the release. ".repeat(200);
+        let notes = vec![
+            scored_note("/notes/a.md", "Release is on Friday."),
+            scored_note("/notes/b.md", "release is on\nFRIDAY."),
+            scored_note("/notes/c.md", &long),
+            scored_note("/notes/d.md", "Never reached"),
+        ];
+
+        let context = build_context(notes, 200);
+        assert_eq!(context.notes.len(), 2);
+        assert!(context.text.starts_with("[1] Note (/notes/a.md)\n"));
+        assert!(context.text.contains("[2] Note (/notes/c.md)\n"));
+        assert!(estimate_tokens(&context.text) <= 200);
+
+        let reasons: Vec<DropReason> = context.dropped.iter().map(|d| d.reason).collect();
+        assert_eq!(reasons, [DropReason::Duplicate, DropReason::OverBudget]);
+    }
+
+    #[test]
+    fn test_build_context_drops_passages_once_budget_is_used_up() {
+        let notes = || {
+            vec![
+                scored_note("/notes/a.md", "Release is on Friday."),
+                scored_note("/notes/b.md", "Deploy after lunch."),
+            ]
+        };
+        // Leaves no more room than the second passage takes, minus the "..." of a cut
+        let first = estimate_tokens("[1] Note (/notes/a.md)\nRelease is on Friday.\n\n");
+        let header = estimate_tokens("[2] Note (/notes/b.md)\n");
+        let second = estimate_tokens("Deploy after lunch.");
+
+        for spare in 0..=second {
+            let context = build_context(notes(), first + header + spare);
+            assert_eq!(context.notes.len(), 1);
+            assert!(!context.text.contains("..."));
+            assert_eq!(context.dropped[0].reason, DropReason::OverBudget);
+        }
+    }
+}
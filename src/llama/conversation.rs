@@ -0,0 +1,133 @@
+// src/llama/conversation.rs
+use crate::llama::answer::NoteSource;
+
+/// Number of earlier messages sent to the model along with a new question
+pub const HISTORY_WINDOW: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+impl Role {
+    /// Name of the role in the chat APIs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        }
+    }
+}
+
+/// A message of a chat conversation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+    /// Notes the answer was based on, empty for user messages
+    pub sources: Vec<NoteSource>,
+    /// Whether the answer was stopped before it was complete. Only shown to the user,
+    /// the model sees the text as it is.
+    pub cancelled: bool,
+}
+
+impl ChatMessage {
+    pub fn new(role: Role, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+            sources: Vec::new(),
+            cancelled: false,
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(Role::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(Role::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(Role::Assistant, content)
+    }
+}
+
+/// Returns the last `max_messages` messages of the conversation, dropping older ones.
+///
+/// The window always starts with a user message, so that the model never sees an
+/// answer without its question. Empty messages, like cancelled answers that produced no
+/// text, are skipped.
+pub fn recent_history(messages: &[ChatMessage], max_messages: usize) -> Vec<&ChatMessage> {
+    let messages: Vec<&ChatMessage> = messages
+        .iter()
+        .filter(|message| message.role != Role::System && !message.content.trim().is_empty())
+        .collect();
+
+    let mut start = messages.len().saturating_sub(max_messages);
+    while messages
+        .get(start)
+        .is_some_and(|message| message.role != Role::User)
+    {
+        start += 1;
+    }
+
+    messages[start..].to_vec()
+}
+
+/// Formats the conversation as a transcript for prompts that are not sent through
+/// the chat API.
+pub fn format_transcript(messages: &[&ChatMessage]) -> String {
+    messages
+        .iter()
+        .map(|message| {
+            let speaker = match message.role {
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+                Role::System => "System",
+            };
+            format!("{}: {}", speaker, message.content.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_history_starts_with_question() {
+        let messages = vec![
+            ChatMessage::assistant("Welcome!"),
+            ChatMessage::user("List my projects"),
+            ChatMessage::assistant("Apollo and Gemini"),
+            ChatMessage::user("What about the second one?"),
+            ChatMessage::assistant(""),
+        ];
+
+        let history = recent_history(&messages, 10);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[0].content, "List my projects");
+
+        // Cutting the window after a question drops the orphaned answer
+        let history = recent_history(&messages, 2);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].content, "What about the second one?");
+    }
+
+    #[test]
+    fn test_format_transcript() {
+        let question = ChatMessage::user("List my projects");
+        let answer = ChatMessage::assistant("Apollo and Gemini\n");
+
+        assert_eq!(
+            format_transcript(&[&question, &answer]),
+            "User: List my projects\nAssistant: Apollo and Gemini"
+        );
+    }
+}
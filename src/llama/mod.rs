@@ -1,137 +1,262 @@
 // src/llama/mod.rs
-use ollama_rs::generation::completion::request::GenerationRequest;
-use ollama_rs::generation::embeddings::request::{EmbeddingsInput, GenerateEmbeddingsRequest};
-use ollama_rs::Ollama;
+use std::sync::Arc;
 
+use crate::config::app_config::{AppConfiguration, LlmProviderKind, PROMPT_NOTES_PLACEHOLDER};
+use crate::llama::answer::{NoteSource, RagAnswer};
+use crate::llama::context::build_context;
+use crate::llama::conversation::{
+    format_transcript, recent_history, ChatMessage, Role, HISTORY_WINDOW,
+};
+use crate::llama::ollama::{OllamaProvider, DEFAULT_OLLAMA_URL};
+use crate::llama::openai::{OpenAiProvider, DEFAULT_OPENAI_URL};
+use crate::llama::provider::{EmbeddingProvider, LlmProvider, UnavailableProvider};
 use crate::prelude::*;
-use std::fs;
-use std::path::PathBuf;
+use crate::rag::filters::SearchFilter;
+use crate::rag::frontmatter::MarkdownNote;
+use crate::rag::store::EmbeddingSpec;
+use log::info;
+use std::path::Path;
+use tokio::sync::mpsc::UnboundedSender;
 
-pub mod consts {
-    pub const AI_MODEL: &str = "gemma3:4b";
-    pub const EMBEDDING_MODEL: &str = "nomic-embed-text"; // or another appropriate embedding model
-    pub const EMBEDDING_SIZE: usize = 768; // Update this to match your model's embedding size
-}
+pub mod answer;
+pub mod context;
+pub mod conversation;
+pub mod ollama;
+pub mod openai;
+pub mod provider;
+
+/// Text embedded to find the dimension of the embedding model
+const EMBEDDING_PROBE_TEXT: &str = "Raggy Notes";
 
+#[derive(Clone)]
 pub struct LlamaService {
-    client: Ollama,
+    llm: Arc<dyn LlmProvider>,
+    embeddings: Arc<dyn EmbeddingProvider>,
+    rag: RagOptions,
+}
+
+/// How questions are answered from the notes
+#[derive(Debug, Clone)]
+pub struct RagOptions {
+    /// Number of note chunks retrieved for a question
+    pub top_k: u64,
+    /// Token budget for the notes in a prompt
+    pub context_tokens: usize,
+    /// System prompt with a `{notes}` placeholder
+    pub prompt_template: String,
+}
+
+impl RagOptions {
+    pub fn from_config(config: &AppConfiguration) -> Self {
+        Self {
+            top_k: config.top_k,
+            context_tokens: config.context_tokens,
+            prompt_template: config.prompt_template.clone(),
+        }
+    }
 }
 
 impl LlamaService {
-    pub fn new(host: &str, port: u16) -> Self {
+    pub fn new(
+        llm: Arc<dyn LlmProvider>,
+        embeddings: Arc<dyn EmbeddingProvider>,
+        rag: RagOptions,
+    ) -> Self {
         Self {
-            client: Ollama::new(host, port),
+            llm,
+            embeddings,
+            rag,
         }
     }
 
-    pub async fn test_connection(&self) -> Result<Vec<String>> {
-        let models = self.client.list_local_models().await?;
-        let models_name_list: Vec<String> = models
-            .iter()
-            .map(|loc_model| loc_model.name.to_owned())
-            .collect();
+    /// Creates the provider selected by `AppConfiguration::llm_provider`, used for both
+    /// completions and embeddings.
+    pub fn from_config(config: &AppConfiguration) -> Result<Self> {
+        let api_key = match &config.llm_api_key_env {
+            Some(variable) => Some(std::env::var(variable).map_err(|_| {
+                AppError::ConfigError(format!(
+                    "Environment variable {} with the API key is not set",
+                    variable
+                ))
+            })?),
+            None => None,
+        };
 
-        Ok(models_name_list)
+        match config.llm_provider {
+            LlmProviderKind::Ollama => {
+                let provider = Arc::new(OllamaProvider::new(
+                    config.llm_base_url.as_deref().unwrap_or(DEFAULT_OLLAMA_URL),
+                    &config.completion_model,
+                    &config.embedding_model,
+                )?);
+                Ok(Self::new(
+                    provider.clone(),
+                    provider,
+                    RagOptions::from_config(config),
+                ))
+            }
+            LlmProviderKind::OpenAi => {
+                let provider = Arc::new(OpenAiProvider::new(
+                    config.llm_base_url.as_deref().unwrap_or(DEFAULT_OPENAI_URL),
+                    api_key,
+                    &config.completion_model,
+                    &config.embedding_model,
+                ));
+                Ok(Self::new(
+                    provider.clone(),
+                    provider,
+                    RagOptions::from_config(config),
+                ))
+            }
+        }
     }
 
-    pub async fn get_embedding(&self, text: &str) -> Result<Vec<Vec<f32>>> {
-        let request = GenerateEmbeddingsRequest::new(
-            consts::EMBEDDING_MODEL.to_string(),
-            EmbeddingsInput::Single(text.to_string()),
-        );
+    /// Creates a service failing every request with `error`, for when the provider can't
+    /// be created from `config`.
+    pub fn unavailable(config: &AppConfiguration, error: AppError) -> Self {
+        let reason = match error {
+            AppError::ConfigError(reason) => reason,
+            e => e.to_string(),
+        };
+        let provider = Arc::new(UnavailableProvider::new(reason, &config.embedding_model));
+        Self::new(provider.clone(), provider, RagOptions::from_config(config))
+    }
 
-        let response = self.client.generate_embeddings(request).await?;
+    /// Returns a copy of the service retrieving `top_k` note chunks per question.
+    pub fn with_top_k(&self, top_k: u64) -> Self {
+        let mut service = self.clone();
+        service.rag.top_k = top_k;
+        service
+    }
 
-        Ok(response.embeddings)
+    pub async fn test_connection(&self) -> Result<Vec<String>> {
+        self.llm.list_models().await
     }
 
-    pub async fn generate_completion(&self, prompt: &str) -> Result<String> {
-        let request = GenerationRequest::new(consts::AI_MODEL.to_string(), prompt.to_string());
+    pub async fn get_embedding(&self, text: &str) -> Result<Vec<Vec<f32>>> {
+        self.embeddings.embed(text).await
+    }
+
+    /// Embeds a probe text to find out the dimension of the embedding model's vectors.
+    pub async fn embedding_spec(&self) -> Result<EmbeddingSpec> {
+        let model = self.embeddings.embedding_model();
+        let dimension = self
+            .get_embedding(EMBEDDING_PROBE_TEXT)
+            .await?
+            .first()
+            .map_or(0, Vec::len);
+        if dimension == 0 {
+            return Err(AppError::LlmError(format!(
+                "Embedding model '{}' returned no vector",
+                model
+            )));
+        }
 
-        let response = self.client.generate(request).await?;
-        Ok(response.response)
+        Ok(EmbeddingSpec {
+            model: model.to_string(),
+            dimension,
+        })
     }
 
-    pub async fn extract_text_from_markdown(
-        &self,
-        file_path: &PathBuf,
-    ) -> Result<(String, String)> {
-        let content = fs::read_to_string(file_path)?;
-        let title = file_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("Untitled")
-            .to_string();
-
-        Ok((title, content))
+    /// Splits a note into its frontmatter metadata and the body that gets embedded.
+    pub fn extract_text_from_markdown(&self, file_path: &Path, content: &str) -> MarkdownNote {
+        MarkdownNote::parse(file_path, content)
     }
-    pub async fn generate_rag_completion(
+
+    /// Turns a follow-up question into a standalone search query using the earlier
+    /// messages, so that "what about the second one?" finds the right notes.
+    ///
+    /// Returns the question unchanged when there is no history to resolve it against.
+    pub async fn rewrite_query(&self, history: &[&ChatMessage], question: &str) -> Result<String> {
+        if !history.iter().any(|message| message.role == Role::User) {
+            return Ok(question.to_string());
+        }
+
+        let prompt = format!(
+            "Rewrite the follow-up question as a standalone search query for the user's notes, \
+        resolving references to the conversation. Reply with the query only.\n\n\
+        Conversation:\n{}\n\n\
+        Follow-up question: {}\n\
+        Standalone query:",
+            format_transcript(history),
+            question
+        );
+
+        let rewritten = self.llm.complete(&prompt).await?;
+        let rewritten = rewritten.trim().trim_matches('"').trim();
+        if rewritten.is_empty() {
+            return Ok(question.to_string());
+        }
+
+        info!("Rewrote '{}' as '{}'", question, rewritten);
+        Ok(rewritten.to_string())
+    }
+
+    /// Answers the latest question of a conversation using the notes most relevant to it,
+    /// restricted by `filter`. `history` holds the earlier messages; only the most recent
+    /// ones are sent to the model.
+    ///
+    /// The answer is sent through `tokens` as it is generated, and generation stops
+    /// early when the receiver is dropped. Returns the answer generated so far along with
+    /// the notes it was based on, numbered as they were cited.
+    pub async fn generate_rag_completion_stream(
         &self,
-        user_query: &str,
+        history: &[ChatMessage],
+        question: &str,
         vector_db: &crate::rag::vectors::VectorDB,
-    ) -> Result<String> {
-        // Step 1: Generate embedding for the user query
-        let embedding = self.get_embedding(user_query).await?;
+        filter: &SearchFilter,
+        tokens: UnboundedSender<String>,
+    ) -> Result<RagAnswer> {
+        let history = recent_history(history, HISTORY_WINDOW);
 
-        // Step 2: Search for relevant notes using the embedding
-        let search_results = vector_db.search_similar_notes(embedding, 5).await?;
+        // Step 1: Resolve the question against the conversation and embed it
+        let search_query = self.rewrite_query(&history, question).await?;
+        let embedding = self.get_embedding(&search_query).await?;
 
-        // Step 3: Prepare context from relevant notes
-        let mut context = String::new();
+        // Step 2: Search for relevant notes by meaning and keywords
+        let search_results = vector_db
+            .search_notes(&search_query, embedding, self.rag.top_k, filter)
+            .await?;
 
-        if search_results.result.is_empty() {
-            context = "No relevant notes found.".to_string();
-        } else {
-            context.push_str("Here are some relevant notes from your knowledge base:\n\n");
-
-            for (i, point) in search_results.result.iter().enumerate() {
-                // Extract title and content from payload
-                let title = point
-                    .payload
-                    .get("title")
-                    .and_then(|v| v.as_str())
-                    .map(|v| v.to_string())
-                    .unwrap_or_else(|| "Untitled".to_string());
-
-                let content = point
-                    .payload
-                    .get("content")
-                    .and_then(|v| v.as_str())
-                    .map(|v| v.to_string())
-                    .unwrap_or_else(|| "No content".to_string());
-
-                // Add a snippet of the note content (to avoid exceeding context window)
-                let content_snippet = if content.len() > 500 {
-                    format!("{}...", &content[..500])
-                } else {
-                    content.to_string()
-                };
-
-                // Add to context
-                context.push_str(&format!(
-                    "Note {}: {} (relevance: {:.2})\n{}\n\n",
-                    i + 1,
-                    title,
-                    point.score,
-                    content_snippet
-                ));
-            }
+        // Step 3: Fill the token budget with the best passages
+        let context = build_context(search_results, self.rag.context_tokens);
+        for dropped in &context.dropped {
+            info!(
+                "Left {} ({}) out of the context: {:?}",
+                dropped.section, dropped.file_path, dropped.reason
+            );
         }
 
-        // Step 4: Build the augmented prompt
-        let augmented_prompt = format!(
-            "You are a helpful AI assistant with access to the user's notes. \
-        Answer the following question using the provided notes when relevant. \
-        If the notes don't contain relevant information, just answer based on your knowledge.\n\n\
-        {}\n\n\
-        User question: {}\n\
-        Helpful answer:",
-            context, user_query
-        );
+        let notes = if context.notes.is_empty() {
+            "No relevant notes found.".to_string()
+        } else {
+            format!(
+                "Here are some relevant notes from your knowledge base. \
+                 Cite the notes you use by their number, like [1] or [2].\n\n{}",
+                context.text
+            )
+        };
+
+        // Step 4: Build the conversation with the notes in the system message
+        let mut messages = vec![ChatMessage::system(
+            self.rag
+                .prompt_template
+                .replace(PROMPT_NOTES_PLACEHOLDER, &notes),
+        )];
+        messages.extend(history.into_iter().cloned());
+        messages.push(ChatMessage::user(question));
 
-        // Step 5: Generate completion with the augmented prompt
-        let response = self.generate_completion(&augmented_prompt).await?;
+        // Step 5: Stream the answer
+        let answer = self.llm.chat_stream(&messages, &tokens).await?;
 
-        Ok(response)
+        Ok(RagAnswer {
+            answer,
+            sources: context
+                .notes
+                .iter()
+                .map(NoteSource::from_scored_note)
+                .collect(),
+            dropped: context.dropped,
+        })
     }
 }
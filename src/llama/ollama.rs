@@ -0,0 +1,232 @@
+// src/llama/ollama.rs
+use async_trait::async_trait;
+use ollama_rs::generation::completion::request::GenerationRequest;
+use ollama_rs::generation::embeddings::request::{EmbeddingsInput, GenerateEmbeddingsRequest};
+use ollama_rs::Ollama;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::llama::conversation::ChatMessage;
+use crate::llama::provider::{EmbeddingProvider, LlmProvider};
+use crate::prelude::*;
+
+/// Default address of a local Ollama server
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Talks to an Ollama server through its native API.
+pub struct OllamaProvider {
+    client: Ollama,
+    /// Client for the chat stream
+    http: Client,
+    completion_model: String,
+    embedding_model: String,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<RequestMessage<'a>>,
+    stream: bool,
+}
+
+#[derive(Serialize)]
+struct RequestMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+/// A line of the chat stream, each a JSON object
+#[derive(Deserialize)]
+struct ChatStreamChunk {
+    message: Option<StreamMessage>,
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamMessage {
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Debug, PartialEq)]
+enum StreamLine {
+    Token(String),
+    Done,
+    Ignored,
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: &str, completion_model: &str, embedding_model: &str) -> Result<Self> {
+        let client = Ollama::try_new(base_url).map_err(|e| {
+            AppError::ConfigError(format!("Invalid Ollama URL '{}': {}", base_url, e))
+        })?;
+
+        Ok(Self {
+            client,
+            http: Client::new(),
+            completion_model: completion_model.to_string(),
+            embedding_model: embedding_model.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let models = self.client.list_local_models().await?;
+        let models_name_list: Vec<String> = models
+            .iter()
+            .map(|loc_model| loc_model.name.to_owned())
+            .collect();
+
+        Ok(models_name_list)
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        let request = GenerationRequest::new(self.completion_model.clone(), prompt.to_string());
+
+        let response = self.client.generate(request).await?;
+        Ok(response.response)
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tokens: &UnboundedSender<String>,
+    ) -> Result<String> {
+        // Streamed without ollama-rs, which drops the cause of errors in the stream
+        let url = format!("{}api/chat", self.client.url_str());
+        let request = ChatRequest {
+            model: &self.completion_model,
+            messages: messages
+                .iter()
+                .map(|message| RequestMessage {
+                    role: message.role.as_str(),
+                    content: &message.content,
+                })
+                .collect(),
+            stream: true,
+        };
+
+        let mut response = self
+            .http
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| AppError::LlmError(format!("Request to {} failed: {}", url, e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::LlmError(format!(
+                "{} returned {}: {}",
+                url, status, body
+            )));
+        }
+
+        let mut completion = String::new();
+        // Bytes of a line that was split across network chunks
+        let mut pending: Vec<u8> = Vec::new();
+
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| AppError::LlmError(format!("Ollama chat stream was interrupted: {}", e)))?
+        {
+            pending.extend_from_slice(&chunk);
+
+            while let Some(line_end) = pending.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = pending.drain(..=line_end).collect();
+                match parse_stream_line(&String::from_utf8_lossy(&line))? {
+                    StreamLine::Token(token) => {
+                        completion.push_str(&token);
+                        if tokens.send(token).is_err() {
+                            return Ok(completion);
+                        }
+                    }
+                    StreamLine::Done => return Ok(completion),
+                    StreamLine::Ignored => {}
+                }
+            }
+        }
+
+        Ok(completion)
+    }
+}
+
+/// Parses a line of the chat stream, failing on errors Ollama reports in the stream,
+/// such as the model running out of memory.
+fn parse_stream_line(line: &str) -> Result<StreamLine> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(StreamLine::Ignored);
+    }
+
+    let chunk: ChatStreamChunk = serde_json::from_str(line).map_err(|e| {
+        AppError::LlmError(format!("Invalid line in the Ollama chat stream: {}", e))
+    })?;
+    if let Some(error) = chunk.error {
+        return Err(AppError::LlmError(format!(
+            "Ollama chat stream failed: {}",
+            error
+        )));
+    }
+
+    let token = chunk
+        .message
+        .map(|message| message.content)
+        .unwrap_or_default();
+    Ok(if !token.is_empty() {
+        StreamLine::Token(token)
+    } else if chunk.done {
+        StreamLine::Done
+    } else {
+        StreamLine::Ignored
+    })
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaProvider {
+    fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<Vec<f32>>> {
+        let request = GenerateEmbeddingsRequest::new(
+            self.embedding_model.clone(),
+            EmbeddingsInput::Single(text.to_string()),
+        );
+
+        let response = self.client.generate_embeddings(request).await?;
+
+        Ok(response.embeddings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_line() {
+        assert_eq!(
+            parse_stream_line(r#"{"message":{"role":"assistant","content":"Hel"},"done":false}"#)
+                .unwrap(),
+            StreamLine::Token("Hel".to_string())
+        );
+        assert_eq!(
+            parse_stream_line(r#"{"message":{"role":"assistant","content":""},"done":true}"#)
+                .unwrap(),
+            StreamLine::Done
+        );
+        assert_eq!(parse_stream_line("\n").unwrap(), StreamLine::Ignored);
+
+        let error = parse_stream_line(r#"{"error":"model requires more system memory"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("model requires more system memory"));
+    }
+}
@@ -0,0 +1,304 @@
+// src/llama/openai.rs
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::llama::conversation::ChatMessage;
+use crate::llama::provider::{EmbeddingProvider, LlmProvider};
+use crate::prelude::*;
+
+/// Default address of a local llama.cpp server
+pub const DEFAULT_OPENAI_URL: &str = "http://localhost:8080/v1";
+
+/// Talks to any server implementing the OpenAI HTTP API, such as llama.cpp server,
+/// vLLM or LM Studio.
+pub struct OpenAiProvider {
+    client: Client,
+    /// API root including the version, e.g. `http://localhost:8080/v1`
+    base_url: String,
+    api_key: Option<String>,
+    completion_model: String,
+    embedding_model: String,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<RequestMessage<'a>>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+}
+
+#[derive(Serialize)]
+struct RequestMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatResponseMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// One server-sent event of a streamed chat completion
+#[derive(Deserialize)]
+struct ChatStreamChunk {
+    choices: Vec<ChatStreamChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatStreamChoice {
+    delta: ChatResponseMessage,
+}
+
+#[derive(Debug, PartialEq)]
+enum StreamLine {
+    Token(String),
+    Done,
+    Ignored,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+impl OpenAiProvider {
+    pub fn new(
+        base_url: &str,
+        api_key: Option<String>,
+        completion_model: &str,
+        embedding_model: &str,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            completion_model: completion_model.to_string(),
+            embedding_model: embedding_model.to_string(),
+        }
+    }
+
+    fn request(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    /// Sends a request and fails on error statuses.
+    async fn execute(&self, request: RequestBuilder) -> Result<Response> {
+        let response = self.request(request).send().await.map_err(|e| {
+            AppError::LlmError(format!("Request to {} failed: {}", self.base_url, e))
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::LlmError(format!(
+                "{} returned {}: {}",
+                self.base_url, status, body
+            )));
+        }
+
+        Ok(response)
+    }
+
+    async fn send<T: for<'de> Deserialize<'de>>(&self, request: RequestBuilder) -> Result<T> {
+        let response = self.execute(request).await?;
+
+        response.json().await.map_err(|e| {
+            AppError::LlmError(format!("Invalid response from {}: {}", self.base_url, e))
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    async fn list_models(&self) -> Result<Vec<String>> {
+        let models: ModelList = self
+            .send(self.client.get(format!("{}/models", self.base_url)))
+            .await?;
+
+        Ok(models.data.into_iter().map(|model| model.id).collect())
+    }
+
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        let request = ChatRequest {
+            model: &self.completion_model,
+            messages: vec![RequestMessage {
+                role: "user",
+                content: prompt,
+            }],
+            stream: false,
+        };
+
+        let response: ChatResponse = self
+            .send(
+                self.client
+                    .post(format!("{}/chat/completions", self.base_url))
+                    .json(&request),
+            )
+            .await?;
+
+        response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| AppError::LlmError("Completion response contained no message".into()))
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tokens: &UnboundedSender<String>,
+    ) -> Result<String> {
+        let request = ChatRequest {
+            model: &self.completion_model,
+            messages: messages
+                .iter()
+                .map(|message| RequestMessage {
+                    role: message.role.as_str(),
+                    content: &message.content,
+                })
+                .collect(),
+            stream: true,
+        };
+
+        let mut response = self
+            .execute(
+                self.client
+                    .post(format!("{}/chat/completions", self.base_url))
+                    .json(&request),
+            )
+            .await?;
+
+        let mut completion = String::new();
+        // Bytes of an event line that was split across network chunks
+        let mut pending: Vec<u8> = Vec::new();
+
+        while let Some(chunk) = response.chunk().await.map_err(|e| {
+            AppError::LlmError(format!(
+                "Reading stream from {} failed: {}",
+                self.base_url, e
+            ))
+        })? {
+            pending.extend_from_slice(&chunk);
+
+            while let Some(line_end) = pending.iter().position(|&byte| byte == b'\n') {
+                let line: Vec<u8> = pending.drain(..=line_end).collect();
+                match parse_stream_line(&String::from_utf8_lossy(&line)) {
+                    StreamLine::Token(token) => {
+                        completion.push_str(&token);
+                        if tokens.send(token).is_err() {
+                            return Ok(completion);
+                        }
+                    }
+                    StreamLine::Done => return Ok(completion),
+                    StreamLine::Ignored => {}
+                }
+            }
+        }
+
+        Ok(completion)
+    }
+}
+
+/// Parses a line of the server-sent event stream of a chat completion.
+fn parse_stream_line(line: &str) -> StreamLine {
+    let Some(data) = line.trim().strip_prefix("data:") else {
+        return StreamLine::Ignored;
+    };
+    let data = data.trim();
+    if data == "[DONE]" {
+        return StreamLine::Done;
+    }
+
+    serde_json::from_str::<ChatStreamChunk>(data)
+        .ok()
+        .and_then(|chunk| chunk.choices.into_iter().next())
+        .and_then(|choice| choice.delta.content)
+        .filter(|content| !content.is_empty())
+        .map_or(StreamLine::Ignored, StreamLine::Token)
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiProvider {
+    fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<Vec<f32>>> {
+        let request = EmbeddingRequest {
+            model: &self.embedding_model,
+            input: text,
+        };
+
+        let response: EmbeddingResponse = self
+            .send(
+                self.client
+                    .post(format!("{}/embeddings", self.base_url))
+                    .json(&request),
+            )
+            .await?;
+
+        Ok(response
+            .data
+            .into_iter()
+            .map(|data| data.embedding)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_line() {
+        assert_eq!(
+            parse_stream_line(r#"data: {"choices":[{"delta":{"content":"Hel"}}]}"#),
+            StreamLine::Token("Hel".to_string())
+        );
+        assert_eq!(
+            parse_stream_line(r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#),
+            StreamLine::Ignored
+        );
+        assert_eq!(parse_stream_line(": keep-alive"), StreamLine::Ignored);
+        assert_eq!(parse_stream_line("data: [DONE]\r\n"), StreamLine::Done);
+    }
+}
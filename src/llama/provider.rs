@@ -0,0 +1,87 @@
+// src/llama/provider.rs
+use async_trait::async_trait;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::llama::conversation::ChatMessage;
+use crate::prelude::*;
+
+/// Generates text with a language model.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Lists the models the provider can serve.
+    async fn list_models(&self) -> Result<Vec<String>>;
+
+    /// Generates a completion for the prompt with the configured model.
+    async fn complete(&self, prompt: &str) -> Result<String>;
+
+    /// Continues the conversation with the configured model and sends the answer through
+    /// `tokens` piece by piece as the model produces it. Stops early once the receiver
+    /// is dropped.
+    ///
+    /// Returns the text generated so far.
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage],
+        tokens: &UnboundedSender<String>,
+    ) -> Result<String>;
+}
+
+/// Turns text into embedding vectors.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Name of the configured embedding model.
+    fn embedding_model(&self) -> &str;
+
+    /// Embeds the text with the configured model.
+    async fn embed(&self, text: &str) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Stands in for a provider that could not be created from the configuration, failing
+/// every request with the reason.
+pub struct UnavailableProvider {
+    reason: String,
+    embedding_model: String,
+}
+
+impl UnavailableProvider {
+    pub fn new(reason: String, embedding_model: &str) -> Self {
+        Self {
+            reason,
+            embedding_model: embedding_model.to_string(),
+        }
+    }
+
+    fn error(&self) -> AppError {
+        AppError::ConfigError(self.reason.clone())
+    }
+}
+
+#[async_trait]
+impl LlmProvider for UnavailableProvider {
+    async fn list_models(&self) -> Result<Vec<String>> {
+        Err(self.error())
+    }
+
+    async fn complete(&self, _prompt: &str) -> Result<String> {
+        Err(self.error())
+    }
+
+    async fn chat_stream(
+        &self,
+        _messages: &[ChatMessage],
+        _tokens: &UnboundedSender<String>,
+    ) -> Result<String> {
+        Err(self.error())
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for UnavailableProvider {
+    fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
+    async fn embed(&self, _text: &str) -> Result<Vec<Vec<f32>>> {
+        Err(self.error())
+    }
+}
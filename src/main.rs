@@ -2,17 +2,27 @@ use clap::{Parser, Subcommand};
 use config::app_config::AppConfiguration;
 use log::{error, info};
 
+use crate::cli::ask::ask;
+use crate::cli::search::{search, OutputFormat};
+use crate::cli::FilterArgs;
 use crate::llama::LlamaService;
+use crate::mcp::McpServer;
 use crate::prelude::*;
-use crate::rag::files::{get_markdown_files, process_markdown_files};
+use crate::rag::files::{get_markdown_files, process_markdown_files, prune_deleted_notes};
 use crate::rag::vectors::VectorDB;
+use crate::rag::watcher::watch_notes;
+use crate::server::{serve, DEFAULT_PORT};
 use crate::tui::run_app;
+use std::sync::Arc;
 
+mod cli;
 mod config;
 mod error;
 mod llama;
+mod mcp;
 mod prelude;
 mod rag;
+mod server;
 mod tui;
 
 #[derive(Parser)]
@@ -25,10 +35,84 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Index all markdown files in the configured directory
-    Index,
+    Index {
+        /// Re-embed every note, even if its content has not changed
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Re-embed every note in the configured directory
+    Reindex {
+        /// Delete the index and create it anew for the configured embedding model
+        #[arg(long)]
+        recreate: bool,
+    },
+
+    /// Remove indexed notes whose files no longer exist
+    Prune {
+        /// Only list the notes that would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Watch the configured directory and re-index notes as they change
+    Watch,
+
+    /// Answer a question from the notes and print the sources it cites
+    Ask {
+        /// The question; may contain inline filters such as tag:work
+        question: String,
+
+        /// Print the answer, its sources and left out passages as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Number of note chunks to retrieve, instead of the configured top_k
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        top_k: Option<u64>,
+
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+
+    /// Search the notes and print the best matching passages
+    Search {
+        /// Search terms; may contain inline filters such as tag:work
+        query: String,
+
+        /// Maximum number of hits
+        #[arg(long, value_name = "N", default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        limit: u64,
+
+        /// Leave out hits whose embedding similarity to the query is below this, from 0
+        /// to 1, including hits only found by keywords
+        #[arg(long, value_name = "SIMILARITY")]
+        min_score: Option<f32>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+
+    /// Serve search, questions and indexing as an HTTP JSON API on localhost
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = DEFAULT_PORT)]
+        port: u16,
+    },
+
+    /// Serve the notes to coding assistants as a Model Context Protocol server on stdio
+    Mcp,
 
     /// Start the TUI application
-    Tui,
+    Tui {
+        /// Re-index notes in the background as they change on disk
+        #[arg(long)]
+        watch: bool,
+    },
 
     /// Initialize the configuration
     Init {
@@ -45,54 +129,23 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    // Initialize LLM service
-    let llama_service = LlamaService::new("http://localhost", 11434);
-
-    // Check models connection
-    match llama_service.test_connection().await {
-        Ok(models) => {
-            info!("LLM Models available: [{}]", models.join(", "));
-        }
-        Err(e) => {
-            error!("Error connecting to Ollama: {}", e);
-            return Err(AppError::OllamaError(format!(
-                "Failed to connect to Ollama: {}",
-                e
-            )));
-        }
-    }
-
-    // Initialize VectorDB
-    let vector_db = match VectorDB::new() {
-        Ok(db) => db,
-        Err(e) => {
-            error!("Error creating VectorDB client: {}", e);
-            return Err(AppError::VectorDBError(format!(
-                "Failed to create VectorDB client: {}",
-                e
-            )));
-        }
+    // Services use the configured backends, or the defaults before 'init'
+    let mut settings = match &cli.command {
+        // 'init' replaces the configuration, even one that can't be read
+        Some(Commands::Init { .. }) => AppConfiguration::default(),
+        _ => AppConfiguration::load_or_default().map_err(|e| {
+            error!("Error loading configuration: {}", e);
+            AppError::ConfigError(format!("Failed to load configuration: {}", e))
+        })?,
     };
-
-    // Test VectorDB connection
-    if let Err(e) = vector_db.test_connection().await {
-        error!("Error connecting to Qdrant: {}", e);
-        return Err(AppError::VectorDBError(format!(
-            "Failed to connect to Qdrant: {}",
-            e
-        )));
+    if let Some(Commands::Ask {
+        top_k: Some(top_k), ..
+    }) = &cli.command
+    {
+        settings.top_k = *top_k;
     }
 
-    // Initialize VectorDB collections
-    if let Err(e) = vector_db.initialize_collections().await {
-        error!("Error initializing collections: {}", e);
-        return Err(AppError::VectorDBError(format!(
-            "Failed to initialize collections: {}",
-            e
-        )));
-    }
-
-    // Process the specified command
+    // Process the specified command, connecting only to the services it needs
     match &cli.command {
         Some(Commands::Init { scan_path }) => {
             info!("Initializing configuration with scan path: {}", scan_path);
@@ -110,43 +163,99 @@ async fn main() -> Result<()> {
                 }
             }
         }
-        Some(Commands::Index) => {
-            info!("Indexing markdown files...");
-
-            // Load configuration
-            let config = match AppConfiguration::load() {
-                Ok(config) => config,
-                Err(e) => {
-                    error!("Error loading configuration: {}", e);
-                    return Err(AppError::ConfigError(format!(
-                        "Failed to load configuration: {}. Run 'init' command first.",
-                        e
-                    )));
-                }
-            };
+        Some(Commands::Index { force }) => {
+            let config = load_configuration()?;
+            let (llama_service, vector_db) = connect_services(&settings, false).await?;
+            index_notes(&config, &llama_service, &vector_db, *force).await?;
+        }
+        Some(Commands::Reindex { recreate }) => {
+            // Without a configuration there is nothing to index, so keep the collection
+            let config = load_configuration()?;
+            let (llama_service, vector_db) = connect_services(&settings, *recreate).await?;
+            index_notes(&config, &llama_service, &vector_db, true).await?;
+        }
+        Some(Commands::Prune { dry_run }) => {
+            let config = load_configuration()?;
+            let files = get_markdown_files(&config)?;
+            let vector_db = connect_vector_db(&settings).await?;
 
-            // Get markdown files
-            let files = match get_markdown_files(&config) {
-                Ok(files) => files,
+            let pruned = match prune_deleted_notes(&files, &vector_db, *dry_run).await {
+                Ok(pruned) => pruned,
                 Err(e) => {
-                    error!("Error getting markdown files: {}", e);
+                    error!("Error pruning deleted notes: {}", e);
                     return Err(e);
                 }
             };
 
-            info!("Found {} markdown files to process", files.len());
-
-            // Process markdown files
-            if let Err(e) = process_markdown_files(&files, &llama_service, &vector_db).await {
-                error!("Error processing markdown files: {}", e);
+            let verb = if *dry_run { "Would remove" } else { "Removed" };
+            for file_path in &pruned {
+                println!("{}: {}", verb, file_path);
+            }
+            println!("{} vectors of {} missing notes", verb, pruned.len());
+        }
+        Some(Commands::Watch) => {
+            let config = load_configuration()?;
+            let (llama_service, vector_db) = connect_services(&settings, false).await?;
+            if let Err(e) = watch_notes(&config, &llama_service, &vector_db, None).await {
+                error!("Error watching notes: {}", e);
+                return Err(e);
+            }
+        }
+        Some(Commands::Ask {
+            question,
+            json,
+            filters,
+            ..
+        }) => {
+            let (llama_service, vector_db) = connect_services(&settings, false).await?;
+            if let Err(e) = ask(question, filters, *json, &llama_service, &vector_db).await {
+                error!("Error answering the question: {}", e);
+                return Err(e);
+            }
+        }
+        Some(Commands::Search {
+            query,
+            limit,
+            min_score,
+            format,
+            filters,
+        }) => {
+            let (llama_service, vector_db) = connect_services(&settings, false).await?;
+            let searched = search(
+                query,
+                filters,
+                *limit,
+                *min_score,
+                *format,
+                &llama_service,
+                &vector_db,
+            )
+            .await;
+            if let Err(e) = searched {
+                error!("Error searching the notes: {}", e);
                 return Err(e);
             }
-
-            info!("Indexing completed successfully");
         }
-        Some(Commands::Tui) => {
+        Some(Commands::Serve { port }) => {
+            let config = load_configuration()?;
+            let (llama_service, vector_db) = connect_services(&settings, false).await?;
+            if let Err(e) = serve(config, llama_service, vector_db, *port).await {
+                error!("Error serving the HTTP API: {}", e);
+                return Err(e);
+            }
+        }
+        Some(Commands::Mcp) => {
+            let config = load_configuration()?;
+            let (llama_service, vector_db) = connect_services(&settings, false).await?;
+            let server = McpServer::new(config, llama_service, vector_db);
+            if let Err(e) = server.run().await {
+                error!("Error serving MCP: {}", e);
+                return Err(e);
+            }
+        }
+        Some(Commands::Tui { watch }) => {
             info!("Starting TUI application...");
-            if let Err(e) = run_app(&llama_service, &vector_db).await {
+            if let Err(e) = start_tui(settings, *watch).await {
                 error!("Error running TUI application: {}", e);
                 return Err(e);
             }
@@ -154,7 +263,7 @@ async fn main() -> Result<()> {
         None => {
             // If no command is specified, default to TUI
             info!("No command specified, starting TUI application...");
-            if let Err(e) = run_app(&llama_service, &vector_db).await {
+            if let Err(e) = start_tui(settings, false).await {
                 error!("Error running TUI application: {}", e);
                 return Err(e);
             }
@@ -165,6 +274,154 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Connects to the LLM provider and the vector store, and prepares the collection for
+/// the embedding model. With `recreate` set, the collection is deleted and created anew.
+async fn connect_services(
+    settings: &AppConfiguration,
+    recreate: bool,
+) -> Result<(Arc<LlamaService>, Arc<VectorDB>)> {
+    let llama_service = connect_llm(settings).await?;
+    let vector_db = connect_vector_db(settings).await?;
+
+    // Probe the embedding model, so that notes are never indexed into a collection of
+    // another model
+    let embedding_spec = match llama_service.embedding_spec().await {
+        Ok(spec) => {
+            info!("Embedding model: {}", spec);
+            spec
+        }
+        Err(e) => {
+            error!("Error probing the embedding model: {}", e);
+            return Err(e);
+        }
+    };
+
+    // Initialize VectorDB collections
+    let initialized = if recreate {
+        info!("Recreating the collection for {}", embedding_spec);
+        vector_db.recreate_collections(&embedding_spec).await
+    } else {
+        vector_db.initialize_collections(&embedding_spec).await
+    };
+    if let Err(e) = initialized {
+        error!("Error initializing collections: {}", e);
+        return Err(e);
+    }
+
+    Ok((llama_service, vector_db))
+}
+
+async fn connect_llm(settings: &AppConfiguration) -> Result<Arc<LlamaService>> {
+    let llama_service = Arc::new(LlamaService::from_config(settings)?);
+
+    // Check models connection
+    match llama_service.test_connection().await {
+        Ok(models) => {
+            info!("LLM Models available: [{}]", models.join(", "));
+        }
+        Err(e) => {
+            error!("Error connecting to the LLM provider: {}", e);
+            return Err(AppError::LlmError(format!(
+                "Failed to connect to the LLM provider: {}",
+                e
+            )));
+        }
+    }
+
+    Ok(llama_service)
+}
+
+async fn connect_vector_db(settings: &AppConfiguration) -> Result<Arc<VectorDB>> {
+    let vector_db = create_vector_db(settings)?;
+
+    // Test VectorDB connection
+    if let Err(e) = vector_db.test_connection().await {
+        let store = settings.vector_store.label();
+        error!("Error connecting to {}: {}", store, e);
+        return Err(AppError::VectorDBError(format!(
+            "Failed to connect to {}: {}",
+            store, e
+        )));
+    }
+
+    Ok(vector_db)
+}
+
+fn create_vector_db(settings: &AppConfiguration) -> Result<Arc<VectorDB>> {
+    match VectorDB::from_config(settings) {
+        Ok(db) => Ok(Arc::new(db)),
+        Err(e) => {
+            error!("Error creating VectorDB client: {}", e);
+            Err(AppError::VectorDBError(format!(
+                "Failed to create VectorDB client: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// Embeds the notes of the configured directory and removes those that no longer exist.
+async fn index_notes(
+    config: &AppConfiguration,
+    llama_service: &LlamaService,
+    vector_db: &VectorDB,
+    force: bool,
+) -> Result<()> {
+    info!("Indexing markdown files...");
+
+    // Get markdown files
+    let files = match get_markdown_files(config) {
+        Ok(files) => files,
+        Err(e) => {
+            error!("Error getting markdown files: {}", e);
+            return Err(e);
+        }
+    };
+
+    info!("Found {} markdown files to process", files.len());
+
+    // Process markdown files
+    if let Err(e) = process_markdown_files(&files, config, llama_service, vector_db, force).await {
+        error!("Error processing markdown files: {}", e);
+        return Err(e);
+    }
+
+    // Remove notes that were deleted or moved since the last run
+    if let Err(e) = prune_deleted_notes(&files, vector_db, false).await {
+        error!("Error pruning deleted notes: {}", e);
+        return Err(e);
+    }
+
+    info!("Indexing completed successfully");
+    Ok(())
+}
+
+/// Runs the TUI, optionally re-indexing changed notes in the background. The TUI
+/// connects to the services itself, so that it starts even if they are unreachable.
+async fn start_tui(settings: AppConfiguration, watch: bool) -> Result<()> {
+    // A provider that can't be created is reported in the TUI, like an unreachable one
+    let llama_service = LlamaService::from_config(&settings)
+        .unwrap_or_else(|e| LlamaService::unavailable(&settings, e));
+    let llama_service = Arc::new(llama_service);
+    let vector_db = create_vector_db(&settings)?;
+
+    // Watching needs the notes directory of an initialized configuration
+    if watch {
+        load_configuration()?;
+    }
+    run_app(settings, llama_service, vector_db, watch).await
+}
+
+fn load_configuration() -> Result<AppConfiguration> {
+    AppConfiguration::load().map_err(|e| {
+        error!("Error loading configuration: {}", e);
+        AppError::ConfigError(format!(
+            "Failed to load configuration: {}. Run 'init' command first.",
+            e
+        ))
+    })
+}
+
 fn initialize_logging() {
     env_logger::init();
 }
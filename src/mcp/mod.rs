@@ -0,0 +1,208 @@
+// src/mcp/mod.rs
+use std::sync::Arc;
+
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{stdin, stdout, AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::config::app_config::AppConfiguration;
+use crate::llama::LlamaService;
+use crate::prelude::*;
+use crate::rag::vectors::VectorDB;
+
+pub mod tools;
+
+/// Protocol revisions the server can speak, newest first
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC request, or a notification when it has no `id`
+#[derive(Debug, Deserialize)]
+struct Message {
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Failure of a request, sent back as a JSON-RPC error
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Services the tools are backed by
+pub struct McpServer {
+    config: AppConfiguration,
+    llama_service: Arc<LlamaService>,
+    vector_db: Arc<VectorDB>,
+}
+
+impl McpServer {
+    pub fn new(
+        config: AppConfiguration,
+        llama_service: Arc<LlamaService>,
+        vector_db: Arc<VectorDB>,
+    ) -> Self {
+        Self {
+            config,
+            llama_service,
+            vector_db,
+        }
+    }
+
+    /// Serves Model Context Protocol messages from stdin until it is closed. Every
+    /// message is a line of JSON and responses are written to stdout, so logs must go
+    /// to stderr.
+    pub async fn run(&self) -> Result<()> {
+        info!("Serving MCP over stdio");
+        let mut lines = BufReader::new(stdin()).lines();
+        let mut stdout = stdout();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if let Some(response) = self.handle_line(&line).await {
+                let mut response = serde_json::to_vec(&response)?;
+                response.push(b'\n');
+                stdout.write_all(&response).await?;
+                stdout.flush().await?;
+            }
+        }
+
+        info!("MCP client disconnected");
+        Ok(())
+    }
+
+    /// Handles a message and returns its response, or `None` for notifications.
+    async fn handle_line(&self, line: &str) -> Option<Value> {
+        let message: Message = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Invalid MCP message: {}", e);
+                let error = RpcError::new(PARSE_ERROR, format!("Invalid message: {}", e));
+                return Some(response(Value::Null, Err(error)));
+            }
+        };
+
+        let Some(id) = message.id else {
+            debug!("MCP notification: {}", message.method);
+            return None;
+        };
+
+        debug!("MCP request: {}", message.method);
+        Some(response(
+            id,
+            self.handle_request(&message.method, message.params).await,
+        ))
+    }
+
+    async fn handle_request(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> std::result::Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(initialize_result(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools::definitions() })),
+            "tools/call" => self.call_tool(params).await,
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            )),
+        }
+    }
+
+    /// Runs a tool. Failures of the tool itself are reported in the result, so that the
+    /// model gets to see them.
+    async fn call_tool(&self, params: Value) -> std::result::Result<Value, RpcError> {
+        #[derive(Deserialize)]
+        struct ToolCall {
+            name: String,
+            #[serde(default)]
+            arguments: Value,
+        }
+
+        let call: ToolCall = serde_json::from_value(params)
+            .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid tool call: {}", e)))?;
+        let tool = tools::Tool::from_name(&call.name)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Unknown tool: {}", call.name)))?;
+
+        let (text, is_error) = match tool.call(call.arguments, self).await {
+            Ok(text) => (text, false),
+            Err(e) => {
+                warn!("MCP tool {} failed: {}", call.name, e);
+                (e.to_string(), true)
+            }
+        };
+        Ok(json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": is_error,
+        }))
+    }
+}
+
+/// Agrees on the protocol revision requested by the client if it is supported, or
+/// offers the newest one otherwise.
+fn initialize_result(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or_default();
+    let version = PROTOCOL_VERSIONS
+        .into_iter()
+        .find(|version| *version == requested)
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "instructions": "Search and read the user's markdown notes, and answer questions from them with citations.",
+    })
+}
+
+fn response(id: Value, result: std::result::Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initialize_negotiates_protocol_version() {
+        let result = initialize_result(&json!({ "protocolVersion": "2024-11-05" }));
+        assert_eq!(result["protocolVersion"], "2024-11-05");
+        assert!(result["capabilities"]["tools"].is_object());
+
+        let result = initialize_result(&json!({ "protocolVersion": "1999-01-01" }));
+        assert_eq!(result["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+}
@@ -0,0 +1,371 @@
+// src/mcp/tools.rs
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::cli::ask::print_sources;
+use crate::cli::FilterArgs;
+use crate::mcp::McpServer;
+use crate::prelude::*;
+use crate::rag::vectors::NotePayload;
+
+/// Default number of passages returned by `search_notes`
+const DEFAULT_SEARCH_LIMIT: u64 = 5;
+
+/// Tools offered to MCP clients
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tool {
+    SearchNotes,
+    GetNote,
+    AskNotes,
+    ListTags,
+}
+
+const TOOLS: [Tool; 4] = [
+    Tool::SearchNotes,
+    Tool::GetNote,
+    Tool::AskNotes,
+    Tool::ListTags,
+];
+
+#[derive(Debug, Deserialize)]
+struct SearchArguments {
+    query: String,
+    limit: Option<u64>,
+    #[serde(flatten)]
+    filters: FilterArgs,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetNoteArguments {
+    path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AskArguments {
+    question: String,
+    top_k: Option<u64>,
+    #[serde(flatten)]
+    filters: FilterArgs,
+}
+
+impl Tool {
+    pub fn from_name(name: &str) -> Option<Self> {
+        TOOLS.into_iter().find(|tool| tool.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::SearchNotes => "search_notes",
+            Tool::GetNote => "get_note",
+            Tool::AskNotes => "ask_notes",
+            Tool::ListTags => "list_tags",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Tool::SearchNotes => {
+                "Search the notes by meaning and keywords. Returns the best matching \
+                 passages with their note path and section, best first."
+            }
+            Tool::GetNote => {
+                "Read a whole note by the path returned by search_notes, or relative to \
+                 the notes directory."
+            }
+            Tool::AskNotes => {
+                "Answer a question from the notes with the local model. The answer cites \
+                 the notes it is based on."
+            }
+            Tool::ListTags => "List the tags used in the notes with their number of notes.",
+        }
+    }
+
+    fn input_schema(&self) -> Value {
+        let filters = json!({
+            "tags": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Only notes with all of these tags",
+            },
+            "path": {
+                "type": "string",
+                "description": "Only notes in this folder, relative to the notes directory",
+            },
+            "after": {
+                "type": "string",
+                "format": "date",
+                "description": "Only notes modified on or after this day (YYYY-MM-DD)",
+            },
+            "before": {
+                "type": "string",
+                "format": "date",
+                "description": "Only notes modified on or before this day (YYYY-MM-DD)",
+            },
+        });
+        let with_filters = |mut properties: Value| {
+            properties
+                .as_object_mut()
+                .expect("properties are an object")
+                .extend(filters.as_object().cloned().unwrap_or_default());
+            properties
+        };
+
+        match self {
+            Tool::SearchNotes => json!({
+                "type": "object",
+                "properties": with_filters(json!({
+                    "query": { "type": "string", "description": "Search terms" },
+                    "limit": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Maximum number of passages",
+                    },
+                })),
+                "required": ["query"],
+            }),
+            Tool::GetNote => json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path of the note" },
+                },
+                "required": ["path"],
+            }),
+            Tool::AskNotes => json!({
+                "type": "object",
+                "properties": with_filters(json!({
+                    "question": { "type": "string" },
+                    "top_k": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Number of passages to answer from",
+                    },
+                })),
+                "required": ["question"],
+            }),
+            Tool::ListTags => json!({ "type": "object", "properties": {} }),
+        }
+    }
+
+    /// Runs the tool and returns its result as text for the model.
+    pub async fn call(&self, arguments: Value, server: &McpServer) -> Result<String> {
+        match self {
+            Tool::SearchNotes => search_notes(parse_arguments(arguments)?, server).await,
+            Tool::GetNote => get_note(parse_arguments(arguments)?, server).await,
+            Tool::AskNotes => ask_notes(parse_arguments(arguments)?, server).await,
+            Tool::ListTags => list_tags(server).await,
+        }
+    }
+}
+
+/// Describes the tools for `tools/list`.
+pub fn definitions() -> Vec<Value> {
+    TOOLS
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name(),
+                "description": tool.description(),
+                "inputSchema": tool.input_schema(),
+            })
+        })
+        .collect()
+}
+
+fn parse_arguments<T: DeserializeOwned>(arguments: Value) -> Result<T> {
+    serde_json::from_value(arguments)
+        .map_err(|e| AppError::InputError(format!("Invalid arguments: {}", e)))
+}
+
+async fn search_notes(arguments: SearchArguments, server: &McpServer) -> Result<String> {
+    let (query, filter) = arguments.filters.parse_query(&arguments.query);
+    if query.is_empty() {
+        return Err(AppError::InputError("The query has no search terms".into()));
+    }
+
+    let embedding = server.llama_service.get_embedding(&query).await?;
+    let limit = arguments.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).max(1);
+    let hits = server
+        .vector_db
+        .search_notes(&query, embedding, limit, &filter)
+        .await?;
+    if hits.is_empty() {
+        return Ok("No matching notes found.".to_string());
+    }
+
+    Ok(hits
+        .iter()
+        .enumerate()
+        .map(|(i, hit)| {
+            format!(
+                "[{}] {} ({:.2})\nPath: {}\n\n{}",
+                i + 1,
+                hit.payload.section_label(),
+                hit.score,
+                hit.payload.file_path,
+                hit.payload.content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n"))
+}
+
+async fn get_note(arguments: GetNoteArguments, server: &McpServer) -> Result<String> {
+    let mut chunks = server.vector_db.note_chunks(&arguments.path).await?;
+    if chunks.is_empty() && Path::new(&arguments.path).is_relative() {
+        let file_path = Path::new(&server.config.scan_path).join(&arguments.path);
+        chunks = server
+            .vector_db
+            .note_chunks(&file_path.to_string_lossy())
+            .await?;
+    }
+
+    let Some(first) = chunks.first() else {
+        return Err(AppError::InputError(format!(
+            "No indexed note at '{}'",
+            arguments.path
+        )));
+    };
+
+    let mut text = format!("Title: {}\nPath: {}\n", first.title, first.file_path);
+    if !first.tags.is_empty() {
+        text.push_str(&format!("Tags: {}\n", first.tags.join(", ")));
+    }
+    if let Some(date) = &first.date {
+        text.push_str(&format!("Date: {}\n", date));
+    }
+    text.push('\n');
+    text.push_str(&note_text(&chunks));
+    Ok(text)
+}
+
+async fn ask_notes(arguments: AskArguments, server: &McpServer) -> Result<String> {
+    let (question, filter) = arguments.filters.parse_query(&arguments.question);
+    if question.is_empty() {
+        return Err(AppError::InputError("The question is empty".into()));
+    }
+    let llama_service = match arguments.top_k {
+        Some(top_k) => server.llama_service.with_top_k(top_k.max(1)),
+        None => server.llama_service.as_ref().clone(),
+    };
+
+    let (tokens, mut token_receiver) = unbounded_channel();
+    let generate = llama_service.generate_rag_completion_stream(
+        &[],
+        &question,
+        &server.vector_db,
+        &filter,
+        tokens,
+    );
+    // Keep receiving, as generation stops once the receiver is dropped
+    let drain = async { while token_receiver.recv().await.is_some() {} };
+    let (answer, _) = tokio::join!(generate, drain);
+    let answer = answer?;
+
+    let mut sources = Vec::new();
+    print_sources(&mut sources, &answer)?;
+    let text = format!("{}\n{}", answer.answer, String::from_utf8_lossy(&sources));
+    Ok(text.trim_end().to_string())
+}
+
+async fn list_tags(server: &McpServer) -> Result<String> {
+    let mut tags: Vec<(String, usize)> = server.vector_db.tag_counts().await?.into_iter().collect();
+    if tags.is_empty() {
+        return Ok("The notes have no tags.".to_string());
+    }
+
+    // Most used first, then alphabetically
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(tags
+        .iter()
+        .map(|(tag, count)| format!("{} ({})", tag, count))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Puts a note back together from its chunks, in order, leaving out the overlap
+/// between neighbouring chunks. Chunks indexed without byte ranges are joined as they
+/// are.
+fn note_text(chunks: &[NotePayload]) -> String {
+    if chunks.iter().all(|chunk| chunk.byte_end == 0) {
+        return chunks
+            .iter()
+            .map(|chunk| chunk.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+    }
+
+    let mut text = String::new();
+    let mut end = chunks.first().map_or(0, |chunk| chunk.byte_start);
+    for chunk in chunks {
+        if chunk.byte_end <= end && !text.is_empty() {
+            continue;
+        }
+
+        if chunk.byte_start > end {
+            // Only whitespace lies between chunks; keep line breaks so that headings
+            // and paragraphs stay apart
+            text.push_str(if chunk.byte_start - end > 1 {
+                "\n\n"
+            } else {
+                "\n"
+            });
+        }
+        let skip = end.saturating_sub(chunk.byte_start);
+        let mut start = skip.min(chunk.content.len());
+        while !chunk.content.is_char_boundary(start) {
+            start += 1;
+        }
+        text.push_str(&chunk.content[start..]);
+        end = chunk.byte_end;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(content: &str, byte_start: usize) -> NotePayload {
+        NotePayload {
+            title: "Note".to_string(),
+            content: content.to_string(),
+            file_path: "/notes/note.md".to_string(),
+            content_hash: String::new(),
+            heading_path: Vec::new(),
+            chunk_index: 0,
+            byte_start,
+            byte_end: byte_start + content.len(),
+            tags: Vec::new(),
+            aliases: Vec::new(),
+            date: None,
+            frontmatter: Default::default(),
+            folders: Vec::new(),
+            modified_at: 0,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_note_text_removes_overlap() {
+        // "# Title\n\nfirst second third\n## Next\nbody" with an overlapping second chunk
+        let chunks = [
+            chunk("# Title", 0),
+            chunk("first second", 9),
+            chunk("second third", 15),
+            chunk("## Next\nbody", 28),
+        ];
+        assert_eq!(
+            note_text(&chunks),
+            "# Title\n\nfirst second third\n## Next\nbody"
+        );
+
+        let mut unranged = [chunk("a", 0), chunk("b", 0)];
+        unranged.iter_mut().for_each(|chunk| chunk.byte_end = 0);
+        assert_eq!(note_text(&unranged), "a\n\nb");
+    }
+}
@@ -0,0 +1,303 @@
+// src/rag/chunker.rs
+use std::ops::Range;
+
+use crate::config::app_config::AppConfiguration;
+
+/// A contiguous passage of a markdown note that is embedded as a single point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteChunk {
+    /// Position of the chunk within the note, starting at 0.
+    pub index: usize,
+    /// Headings enclosing the chunk, outermost first.
+    pub heading_path: Vec<String>,
+    /// Byte range of the chunk within the note content.
+    pub byte_range: Range<usize>,
+    pub text: String,
+}
+
+impl NoteChunk {
+    /// Text sent to the embedding model: the chunk prefixed with the note title and
+    /// heading path, so that a passage keeps the context of the section it lives in.
+    pub fn embedding_text(&self, title: &str) -> String {
+        let mut header = vec![title.to_string()];
+        header.extend(self.heading_path.iter().cloned());
+        format!("{}\n\n{}", header.join(" > "), self.text)
+    }
+}
+
+/// Splits markdown notes on headings first and then on paragraphs, so that every
+/// chunk fits in `chunk_size` bytes and overlaps its predecessor by `chunk_overlap`.
+#[derive(Debug, Clone)]
+pub struct MarkdownChunker {
+    chunk_size: usize,
+    chunk_overlap: usize,
+}
+
+struct Section {
+    heading_path: Vec<String>,
+    range: Range<usize>,
+}
+
+impl MarkdownChunker {
+    pub fn new(chunk_size: usize, chunk_overlap: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        // An overlap of half a chunk or more would make little progress per chunk
+        let chunk_overlap = chunk_overlap.min(chunk_size / 2);
+
+        Self {
+            chunk_size,
+            chunk_overlap,
+        }
+    }
+
+    pub fn from_config(config: &AppConfiguration) -> Self {
+        Self::new(config.chunk_size, config.chunk_overlap)
+    }
+
+    /// Splits the content into chunks, skipping sections that are only whitespace.
+    pub fn chunk(&self, content: &str) -> Vec<NoteChunk> {
+        let mut chunks = Vec::new();
+
+        for section in split_sections(content) {
+            for range in self.split_section(content, section.range) {
+                chunks.push(NoteChunk {
+                    index: chunks.len(),
+                    heading_path: section.heading_path.clone(),
+                    text: content[range.clone()].to_string(),
+                    byte_range: range,
+                });
+            }
+        }
+
+        chunks
+    }
+
+    fn split_section(&self, content: &str, section: Range<usize>) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = skip_whitespace(content, section.start, section.end);
+        // End of the previous chunk; a chunk has to reach past it to not only repeat the overlap
+        let mut previous_end = start;
+
+        while start < section.end {
+            let limit = floor_char_boundary(content, start + self.chunk_size);
+            if limit >= section.end {
+                push_trimmed(content, &mut ranges, start..section.end);
+                break;
+            }
+
+            let end = find_break(content, previous_end.max(start), limit);
+            push_trimmed(content, &mut ranges, start..end);
+            previous_end = end;
+
+            // Step back by the overlap, but always move forward and start on a word
+            let overlap_start =
+                floor_char_boundary(content, end.saturating_sub(self.chunk_overlap));
+            let next = if overlap_start > start {
+                content[overlap_start..end]
+                    .find(char::is_whitespace)
+                    .map(|pos| overlap_start + pos)
+                    .unwrap_or(end)
+            } else {
+                end
+            };
+            start = skip_whitespace(content, next, section.end);
+        }
+
+        ranges
+    }
+}
+
+/// Follows fenced code blocks through the lines of a note, so that their content is not
+/// taken for markdown structure.
+#[derive(Debug, Default)]
+pub struct CodeFences<'a> {
+    /// Marker of the open fence, if inside one
+    open: Option<&'a str>,
+}
+
+impl<'a> CodeFences<'a> {
+    /// Returns true if the line, given in order, is fenced code or a fence itself.
+    pub fn is_code(&mut self, line: &'a str) -> bool {
+        let trimmed = line.trim_start();
+
+        if let Some(marker) = self.open {
+            if trimmed.starts_with(marker) {
+                self.open = None;
+            }
+            true
+        } else if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            self.open = Some(&trimmed[..3]);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Splits the content into sections at ATX headings that are not inside fenced code.
+fn split_sections(content: &str) -> Vec<Section> {
+    let mut sections = Vec::new();
+    let mut heading_stack: Vec<(usize, String)> = Vec::new();
+    let mut section_start = 0;
+    let mut fences = CodeFences::default();
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        if fences.is_code(line) {
+            // Comments in shell or Python code look like headings
+        } else if let Some((level, heading)) = parse_heading(line) {
+            if offset > section_start {
+                sections.push(Section {
+                    heading_path: heading_stack.iter().map(|(_, h)| h.clone()).collect(),
+                    range: section_start..offset,
+                });
+            }
+
+            while heading_stack.last().is_some_and(|(l, _)| *l >= level) {
+                heading_stack.pop();
+            }
+            heading_stack.push((level, heading));
+            section_start = offset;
+        }
+
+        offset += line.len();
+    }
+
+    if content.len() > section_start {
+        sections.push(Section {
+            heading_path: heading_stack.iter().map(|(_, h)| h.clone()).collect(),
+            range: section_start..content.len(),
+        });
+    }
+
+    sections
+}
+
+/// Parses an ATX heading line (`## Heading ##`) into its level and text.
+fn parse_heading(line: &str) -> Option<(usize, String)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+
+    let rest = &line[indent..];
+    let level = rest.len() - rest.trim_start_matches('#').len();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let text = &rest[level..];
+    if !text.is_empty() && !text.starts_with([' ', '\t', '\r', '\n']) {
+        return None;
+    }
+
+    let text = text.trim().trim_end_matches('#').trim_end();
+    Some((level, text.to_string()))
+}
+
+/// Finds the best place between `start` and `limit` to end a chunk: a paragraph break,
+/// then a line break, then whitespace, then any char boundary.
+fn find_break(content: &str, start: usize, limit: usize) -> usize {
+    if limit <= start {
+        // The chunk size is smaller than a single character
+        return start + content[start..].chars().next().map_or(1, char::len_utf8);
+    }
+
+    let window = &content[start..limit];
+
+    let candidates = [
+        window.rfind("\n\n").map(|pos| pos + 2),
+        window.rfind('\n').map(|pos| pos + 1),
+        window.rfind(char::is_whitespace).map(|pos| pos + 1),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|&pos| pos > 0)
+        .map_or(limit, |pos| start + pos)
+}
+
+fn push_trimmed(content: &str, ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    let trimmed_len = content[range.clone()].trim_end().len();
+    if trimmed_len > 0 {
+        ranges.push(range.start..range.start + trimmed_len);
+    }
+}
+
+fn skip_whitespace(content: &str, from: usize, to: usize) -> usize {
+    let skipped = content[from..to].len() - content[from..to].trim_start().len();
+    from + skipped
+}
+
+fn floor_char_boundary(content: &str, index: usize) -> usize {
+    if index >= content.len() {
+        return content.len();
+    }
+
+    let mut index = index;
+    while !content.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_follow_heading_hierarchy() {
+        let content = "Intro line\n\n# Project\nOverview\n\n## Tasks\n- one\n\n# Other\nText\n";
+        let chunks = MarkdownChunker::new(1000, 0).chunk(content);
+
+        let paths: Vec<Vec<String>> = chunks.iter().map(|c| c.heading_path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                vec![],
+                vec!["Project".to_string()],
+                vec!["Project".to_string(), "Tasks".to_string()],
+                vec!["Other".to_string()],
+            ]
+        );
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.index, i);
+            assert_eq!(&content[chunk.byte_range.clone()], chunk.text);
+        }
+    }
+
+    #[test]
+    fn test_headings_inside_code_fences_are_ignored() {
+        let content = "# Setup\n```bash\n# not a heading\necho hi\n```\n";
+        let chunks = MarkdownChunker::new(1000, 0).chunk(content);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].heading_path, vec!["Setup".to_string()]);
+    }
+
+    #[test]
+    fn test_long_sections_are_split_on_paragraphs_with_overlap() {
+        let paragraph = "zażółć gęślą jaźń ".repeat(10);
+        let content = format!("# Notes\n{}\n\n{}\n\n{}\n", paragraph, paragraph, paragraph);
+        let chunker = MarkdownChunker::new(300, 40);
+        let chunks = chunker.chunk(&content);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.text.len() <= 300);
+            assert_eq!(chunk.heading_path, vec!["Notes".to_string()]);
+            assert_eq!(&content[chunk.byte_range.clone()], chunk.text);
+        }
+        for pair in chunks.windows(2) {
+            assert!(pair[1].byte_range.start < pair[0].byte_range.end);
+            assert!(pair[1].byte_range.start > pair[0].byte_range.start);
+        }
+    }
+
+    #[test]
+    fn test_whitespace_only_content_has_no_chunks() {
+        assert!(MarkdownChunker::new(100, 10).chunk(" \n\n \n").is_empty());
+    }
+}
@@ -1,15 +1,20 @@
 // src/rag/files.rs
 use std::{
-    fs::{read_dir, DirEntry},
-    path::PathBuf,
+    collections::HashSet,
+    fs::{metadata, read_dir, read_to_string, DirEntry},
+    path::{Path, PathBuf},
 };
 
-use log::{info, warn};
+use log::{debug, info, warn};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
 use crate::config::app_config::AppConfiguration;
 use crate::llama::LlamaService;
 use crate::prelude::*;
-use crate::rag::vectors::{NoteVector, VectorDB};
+use crate::rag::chunker::MarkdownChunker;
+use crate::rag::filters::note_folders;
+use crate::rag::vectors::{NoteFile, NoteVector, VectorDB};
 
 /// Retrieves all markdown files from the configured directory.
 pub fn get_markdown_files(config: &AppConfiguration) -> Result<Vec<PathBuf>> {
@@ -33,54 +38,253 @@ fn extract_markdown_files(dir_entry: &DirEntry) -> Result<Vec<PathBuf>> {
         for entry in read_dir(dir_entry.path())?.flatten() {
             markdown_files.extend(extract_markdown_files(&entry)?);
         }
-    } else if let Some(ext) = dir_entry.path().extension() {
-        if ext == "md" {
-            info!("Found markdown file: {:?}", dir_entry.path());
-            markdown_files.push(dir_entry.path());
-        }
+    } else if is_markdown_file(&dir_entry.path()) {
+        info!("Found markdown file: {:?}", dir_entry.path());
+        markdown_files.push(dir_entry.path());
     }
 
     Ok(markdown_files)
 }
 
-/// Processes markdown files, extracts text, generates embeddings, and stores in VectorDB
+/// Outcome of an indexing pass
+#[derive(Debug, Default)]
+pub struct IndexSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+}
+
+/// What indexing a single note did
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexOutcome {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+/// Computes the hash stored with every chunk of a note to detect changed files.
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Processes markdown files, splits them into chunks, generates embeddings, and stores
+/// every chunk as its own point in VectorDB.
+///
+/// Notes whose content hash matches the indexed one are skipped unless `force` is set;
+/// changed notes have their old points replaced.
 pub async fn process_markdown_files(
     files: &[PathBuf],
+    config: &AppConfiguration,
     llama_service: &LlamaService,
     vector_db: &VectorDB,
-) -> Result<()> {
+    force: bool,
+) -> Result<IndexSummary> {
     info!("Processing {} markdown files", files.len());
 
+    let indexed_hashes = vector_db.indexed_note_hashes().await?;
+    let mut summary = IndexSummary::default();
+
     for (i, file_path) in files.iter().enumerate() {
-        info!("Processing file {}/{}: {:?}", i + 1, files.len(), file_path);
+        debug!("Processing file {}/{}: {:?}", i + 1, files.len(), file_path);
 
-        // Extract text from markdown
-        let (title, content) = llama_service.extract_text_from_markdown(file_path).await?;
+        let indexed_hash = indexed_hashes
+            .get(file_path.to_string_lossy().as_ref())
+            .map(String::as_str);
 
-        // Generate embedding
-        info!("Generating embedding for: {}", title);
-        let embeddings = match llama_service.get_embedding(&content).await {
-            Ok(emb) => emb,
+        match index_markdown_file(
+            file_path,
+            indexed_hash,
+            force,
+            config,
+            llama_service,
+            vector_db,
+        )
+        .await
+        {
+            Ok(IndexOutcome::Added) => summary.added += 1,
+            Ok(IndexOutcome::Updated) => summary.updated += 1,
+            Ok(IndexOutcome::Unchanged) => summary.unchanged += 1,
             Err(e) => {
-                warn!("Error generating embedding for {:?}: {}", file_path, e);
-                continue;
+                warn!("Error indexing {:?}: {}", file_path, e);
+                summary.failed += 1;
             }
-        };
-
-        // Create note vector
-        let note_vector = NoteVector::new(
-            title.clone(),
-            content.clone(),
-            file_path.clone(),
-            embeddings,
-        );
-
-        // Store in VectorDB
-        if let Err(e) = vector_db.save_note_vector(note_vector).await {
-            warn!("Error saving vector for {:?}: {}", file_path, e);
         }
     }
 
-    info!("Finished processing all markdown files");
-    Ok(())
+    vector_db.flush().await?;
+
+    info!(
+        "Finished processing markdown files: {} added, {} updated, {} unchanged, {} failed",
+        summary.added, summary.updated, summary.unchanged, summary.failed
+    );
+    Ok(summary)
+}
+
+/// Indexes a single note, replacing the points of its previous version.
+///
+/// `indexed_hash` is the content hash currently stored for the note, if it was indexed
+/// before; the note is skipped when it matches, unless `force` is set.
+pub async fn index_markdown_file(
+    file_path: &Path,
+    indexed_hash: Option<&str>,
+    force: bool,
+    config: &AppConfiguration,
+    llama_service: &LlamaService,
+    vector_db: &VectorDB,
+) -> Result<IndexOutcome> {
+    let content = read_to_string(file_path)?;
+    let hash = content_hash(&content);
+
+    if !force && indexed_hash == Some(hash.as_str()) {
+        debug!("Skipping unchanged file: {:?}", file_path);
+        return Ok(IndexOutcome::Unchanged);
+    }
+
+    info!("Indexing file: {:?}", file_path);
+    let modified_at: chrono::DateTime<chrono::Utc> = metadata(file_path)?.modified()?.into();
+    let relative_path = file_path
+        .strip_prefix(&config.scan_path)
+        .unwrap_or(file_path);
+    let note_file = NoteFile {
+        path: file_path.to_path_buf(),
+        content_hash: hash,
+        folders: note_folders(relative_path),
+        modified_at: modified_at.timestamp(),
+    };
+
+    let chunker = MarkdownChunker::from_config(config);
+    let note_vectors = embed_markdown_file(&note_file, &content, &chunker, llama_service).await?;
+
+    // A note without text (empty, or only frontmatter) has no points to hold its hash,
+    // so it is checked again on every run; nothing changes unless it had points before
+    if note_vectors.is_empty() && indexed_hash.is_none() {
+        debug!("Nothing to index in: {:?}", file_path);
+        return Ok(IndexOutcome::Unchanged);
+    }
+
+    // Store in VectorDB, replacing the points of the previous version
+    vector_db
+        .replace_note_vectors(&file_path.to_string_lossy(), note_vectors)
+        .await?;
+
+    Ok(match indexed_hash {
+        Some(_) => IndexOutcome::Updated,
+        None => IndexOutcome::Added,
+    })
+}
+
+/// Returns true if the path looks like a markdown note.
+pub fn is_markdown_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+}
+
+/// Removes the points of notes that were indexed but no longer exist on disk, e.g.
+/// because they were deleted or moved. Returns the file paths that were pruned.
+///
+/// With `dry_run` set nothing is deleted and the paths that would be pruned are returned.
+pub async fn prune_deleted_notes(
+    files: &[PathBuf],
+    vector_db: &VectorDB,
+    dry_run: bool,
+) -> Result<Vec<String>> {
+    let on_disk: HashSet<String> = files
+        .iter()
+        .map(|file_path| file_path.to_string_lossy().to_string())
+        .collect();
+
+    let mut stale: Vec<String> = vector_db
+        .indexed_note_hashes()
+        .await?
+        .into_keys()
+        .filter(|file_path| !on_disk.contains(file_path))
+        .collect();
+    stale.sort();
+
+    if dry_run {
+        return Ok(stale);
+    }
+
+    for file_path in &stale {
+        info!("Pruning vectors of missing file: {}", file_path);
+        vector_db.delete_note_vectors(file_path).await?;
+    }
+
+    vector_db.flush().await?;
+
+    info!("Pruned vectors of {} missing files", stale.len());
+    Ok(stale)
+}
+
+/// Splits a note into chunks and generates an embedding for each of them.
+async fn embed_markdown_file(
+    note_file: &NoteFile,
+    content: &str,
+    chunker: &MarkdownChunker,
+    llama_service: &LlamaService,
+) -> Result<Vec<NoteVector>> {
+    // Extract frontmatter and text from markdown
+    let note = llama_service.extract_text_from_markdown(&note_file.path, content);
+
+    let chunks = chunker.chunk(&note.body);
+    info!(
+        "Generating embeddings for {} chunks of: {}",
+        chunks.len(),
+        note.title
+    );
+
+    let mut note_vectors = Vec::with_capacity(chunks.len());
+    for mut chunk in chunks {
+        let embeddings = llama_service
+            .get_embedding(&chunk.embedding_text(&note.title))
+            .await?;
+
+        // Chunk ranges are relative to the body; store them relative to the file
+        chunk.byte_range =
+            chunk.byte_range.start + note.body_offset..chunk.byte_range.end + note.body_offset;
+
+        note_vectors.push(NoteVector::new(&note, note_file, chunk, embeddings));
+    }
+
+    Ok(note_vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::rag::store::local::LocalStore;
+
+    #[tokio::test]
+    async fn test_note_without_text_is_not_added() {
+        let dir = TempDir::new("notes").unwrap();
+        let config = AppConfiguration::new(dir.path().to_str().unwrap());
+        for (name, content) in [
+            ("empty.md", ""),
+            ("blank.md", "  \n\n"),
+            ("meta.md", "---\ntags: [todo]\n---\n"),
+        ] {
+            std::fs::write(dir.path().join(name), content).unwrap();
+        }
+        // Notes without text never need the model
+        let llama_service =
+            LlamaService::unavailable(&config, AppError::ConfigError("No model in tests".into()));
+        let store = LocalStore::open(dir.path().join("vectors.json")).unwrap();
+        let vector_db = VectorDB::new(Box::new(store), 0.0);
+
+        let files = get_markdown_files(&config).unwrap();
+        for _ in 0..2 {
+            let summary =
+                process_markdown_files(&files, &config, &llama_service, &vector_db, false)
+                    .await
+                    .unwrap();
+            assert_eq!(summary.unchanged, 3);
+            assert_eq!(summary.added + summary.failed, 0);
+        }
+    }
 }
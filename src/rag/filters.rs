@@ -0,0 +1,253 @@
+// src/rag/filters.rs
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use qdrant_client::qdrant::{Condition, Filter, Range};
+
+use crate::rag::vectors::NotePayload;
+
+/// Restricts a semantic search to a subset of the notes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilter {
+    /// Folder relative to the scan path, e.g. `projects/raggy`
+    pub folder: Option<String>,
+    /// Tags a note must all have
+    pub tags: Vec<String>,
+    pub modified_after: Option<DateTime<Utc>>,
+    pub modified_before: Option<DateTime<Utc>>,
+}
+
+impl SearchFilter {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Builds the Qdrant payload filter, or `None` when nothing is filtered.
+    pub fn to_qdrant(&self) -> Option<Filter> {
+        let mut conditions = Vec::new();
+
+        if let Some(folder) = &self.folder {
+            conditions.push(Condition::matches("folders", folder.clone()));
+        }
+        for tag in &self.tags {
+            conditions.push(Condition::matches("tags", tag.clone()));
+        }
+        if self.modified_after.is_some() || self.modified_before.is_some() {
+            conditions.push(Condition::range(
+                "modified_at",
+                Range {
+                    gte: self.modified_after.map(|d| d.timestamp() as f64),
+                    lte: self.modified_before.map(|d| d.timestamp() as f64),
+                    ..Default::default()
+                },
+            ));
+        }
+
+        if conditions.is_empty() {
+            None
+        } else {
+            Some(Filter::must(conditions))
+        }
+    }
+
+    /// Restricts the filter to a folder relative to the scan path. Returns `false` and
+    /// leaves the filter unchanged for an empty path.
+    pub fn set_folder(&mut self, path: &str) -> bool {
+        match normalize_folder(path) {
+            Some(folder) => {
+                self.folder = Some(folder);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keeps notes modified on or after the day.
+    pub fn set_modified_after(&mut self, date: NaiveDate) {
+        self.modified_after = Some(start_of_day(date));
+    }
+
+    /// Keeps notes modified on or before the day.
+    pub fn set_modified_before(&mut self, date: NaiveDate) {
+        self.modified_before = Some(end_of_day(date));
+    }
+
+    /// Checks a payload against the filter, for backends without payload indexes.
+    pub fn matches(&self, payload: &NotePayload) -> bool {
+        let in_folder = self
+            .folder
+            .as_ref()
+            .is_none_or(|folder| payload.folders.contains(folder));
+        let has_tags = self.tags.iter().all(|tag| payload.tags.contains(tag));
+        let after = self
+            .modified_after
+            .is_none_or(|after| payload.modified_at >= after.timestamp());
+        let before = self
+            .modified_before
+            .is_none_or(|before| payload.modified_at <= before.timestamp());
+
+        in_folder && has_tags && after && before
+    }
+}
+
+impl fmt::Display for SearchFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(folder) = &self.folder {
+            parts.push(format!("path:{}/", folder));
+        }
+        parts.extend(self.tags.iter().map(|tag| format!("tag:{}", tag)));
+        if let Some(after) = self.modified_after {
+            parts.push(format!("after:{}", after.format("%Y-%m-%d")));
+        }
+        if let Some(before) = self.modified_before {
+            parts.push(format!("before:{}", before.format("%Y-%m-%d")));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Splits inline filters such as `tag:work path:projects/ after:2024-01-01` from a query.
+///
+/// Returns the remaining query text and the parsed filter. Terms that look like filters
+/// but have an invalid value are kept as part of the query.
+pub fn parse_query(input: &str) -> (String, SearchFilter) {
+    let mut filter = SearchFilter::default();
+    let mut query_terms = Vec::new();
+
+    for term in input.split_whitespace() {
+        let parsed = match term.split_once(':') {
+            Some(("tag", tag)) if !tag.is_empty() => {
+                filter.tags.push(tag.trim_start_matches('#').to_string());
+                true
+            }
+            Some(("path", path)) => filter.set_folder(path),
+            Some(("after", date)) => parse_date(date)
+                .map(|date| filter.set_modified_after(date))
+                .is_some(),
+            Some(("before", date)) => parse_date(date)
+                .map(|date| filter.set_modified_before(date))
+                .is_some(),
+            _ => false,
+        };
+
+        if !parsed {
+            query_terms.push(term);
+        }
+    }
+
+    (query_terms.join(" "), filter)
+}
+
+/// Returns every ancestor folder of a note relative to the scan path, outermost first,
+/// e.g. `projects` and `projects/raggy` for `projects/raggy/todo.md`.
+pub fn note_folders(relative_path: &std::path::Path) -> Vec<String> {
+    let mut folders = Vec::new();
+    let mut current = String::new();
+
+    if let Some(parent) = relative_path.parent() {
+        for component in parent.components() {
+            let name = component.as_os_str().to_string_lossy();
+            if name == "." {
+                continue;
+            }
+            if !current.is_empty() {
+                current.push('/');
+            }
+            current.push_str(&name);
+            folders.push(current.clone());
+        }
+    }
+
+    folders
+}
+
+fn normalize_folder(path: &str) -> Option<String> {
+    let folder = path.trim_start_matches("./").trim_matches('/');
+    if folder.is_empty() {
+        None
+    } else {
+        Some(folder.to_string())
+    }
+}
+
+/// Parses a `YYYY-MM-DD` date.
+pub fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+fn end_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(23, 59, 59).unwrap_or_default().and_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_query_extracts_inline_filters() {
+        let (query, filter) =
+            parse_query("tag:work what did we decide path:projects/raggy/ after:2024-01-31");
+
+        assert_eq!(query, "what did we decide");
+        assert_eq!(filter.tags, vec!["work"]);
+        assert_eq!(filter.folder.as_deref(), Some("projects/raggy"));
+        assert_eq!(
+            filter.modified_after,
+            Some(start_of_day(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()))
+        );
+        assert_eq!(filter.modified_before, None);
+        assert_eq!(
+            filter.to_string(),
+            "path:projects/raggy/ tag:work after:2024-01-31"
+        );
+    }
+
+    #[test]
+    fn test_invalid_filters_stay_in_query() {
+        let (query, filter) = parse_query("meeting at 10:30 before:tomorrow");
+
+        assert_eq!(query, "meeting at 10:30 before:tomorrow");
+        assert!(filter.is_empty());
+        assert!(filter.to_qdrant().is_none());
+    }
+
+    #[test]
+    fn test_filter_matches_payload() {
+        let payload: NotePayload = serde_json::from_value(serde_json::json!({
+            "title": "Todo",
+            "content": "",
+            "file_path": "/notes/projects/raggy/todo.md",
+            "tags": ["work", "rust"],
+            "folders": ["projects", "projects/raggy"],
+            "modified_at": start_of_day(NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()).timestamp(),
+            "created_at": "2024-03-01T00:00:00Z",
+            "updated_at": "2024-03-01T00:00:00Z",
+        }))
+        .unwrap();
+
+        let (_, filter) = parse_query("tag:work path:projects/ after:2024-02-01 before:2024-03-01");
+        assert!(filter.matches(&payload));
+
+        let (_, filter) = parse_query("tag:home");
+        assert!(!filter.matches(&payload));
+
+        let (_, filter) = parse_query("path:projects/other after:2024-03-02");
+        assert!(!filter.matches(&payload));
+    }
+
+    #[test]
+    fn test_note_folders_lists_ancestors() {
+        assert_eq!(
+            note_folders(Path::new("projects/raggy/todo.md")),
+            vec!["projects", "projects/raggy"]
+        );
+        assert!(note_folders(Path::new("todo.md")).is_empty());
+    }
+}
@@ -0,0 +1,260 @@
+// src/rag/frontmatter.rs
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::rag::chunker::CodeFences;
+
+/// Metadata parsed from a YAML (`---`) or TOML (`+++`) block at the top of a note.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frontmatter {
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    pub date: Option<String>,
+    /// Every other key, with its value kept as typed JSON
+    pub extra: Map<String, Value>,
+}
+
+/// A markdown note split into its frontmatter and the body that gets embedded.
+#[derive(Debug, Clone)]
+pub struct MarkdownNote {
+    pub title: String,
+    pub frontmatter: Frontmatter,
+    pub body: String,
+    /// Byte offset of the body within the file content
+    pub body_offset: usize,
+}
+
+impl MarkdownNote {
+    /// Parses a note. The title comes from the frontmatter, then from the first `# H1`
+    /// heading, then from the file stem.
+    pub fn parse(file_path: &Path, content: &str) -> Self {
+        let (frontmatter, body_offset) = match split_frontmatter(content) {
+            Some((block, body_offset)) => (block.parse(), body_offset),
+            None => (Frontmatter::default(), 0),
+        };
+        let body = &content[body_offset..];
+
+        let title = frontmatter
+            .title
+            .clone()
+            .or_else(|| first_h1(body))
+            .unwrap_or_else(|| {
+                file_path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("Untitled")
+                    .to_string()
+            });
+
+        Self {
+            title,
+            frontmatter,
+            body: body.to_string(),
+            body_offset,
+        }
+    }
+}
+
+enum FrontmatterBlock<'a> {
+    Yaml(&'a str),
+    Toml(&'a str),
+}
+
+impl FrontmatterBlock<'_> {
+    /// Parses the block, falling back to empty metadata if it is malformed.
+    fn parse(&self) -> Frontmatter {
+        let value = match self {
+            FrontmatterBlock::Yaml(source) => serde_yaml::from_str::<Value>(source).ok(),
+            FrontmatterBlock::Toml(source) => source
+                .parse::<toml::Table>()
+                .ok()
+                .map(|table| toml_to_json(toml::Value::Table(table))),
+        };
+
+        match value {
+            Some(Value::Object(fields)) => Frontmatter::from_fields(fields),
+            _ => Frontmatter::default(),
+        }
+    }
+}
+
+impl Frontmatter {
+    fn from_fields(mut fields: Map<String, Value>) -> Self {
+        let title = fields.remove("title").and_then(scalar_to_string);
+        let tags = fields
+            .remove("tags")
+            .map(string_list)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|tag| tag.trim_start_matches('#').to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        let aliases = fields
+            .remove("aliases")
+            .map(string_list)
+            .unwrap_or_default();
+        let date = fields.remove("date").and_then(scalar_to_string);
+
+        Self {
+            title,
+            tags,
+            aliases,
+            date,
+            extra: fields,
+        }
+    }
+}
+
+/// Finds a frontmatter block at the very start of the content and returns it together
+/// with the byte offset at which the body starts.
+fn split_frontmatter(content: &str) -> Option<(FrontmatterBlock<'_>, usize)> {
+    let content_start = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let bom_len = content.len() - content_start.len();
+
+    let delimiter = ["---", "+++"]
+        .into_iter()
+        .find(|delimiter| first_line(content_start) == *delimiter)?;
+
+    let block_start = bom_len + content_start.find('\n')? + 1;
+    let mut offset = block_start;
+
+    for line in content[block_start..].split_inclusive('\n') {
+        let line_end = offset + line.len();
+        if line.trim_end() == delimiter {
+            let source = &content[block_start..offset];
+            let block = match delimiter {
+                "---" => FrontmatterBlock::Yaml(source),
+                _ => FrontmatterBlock::Toml(source),
+            };
+            return Some((block, line_end));
+        }
+        offset = line_end;
+    }
+
+    None
+}
+
+fn first_line(content: &str) -> &str {
+    content.lines().next().unwrap_or_default().trim_end()
+}
+
+fn first_h1(body: &str) -> Option<String> {
+    let mut fences = CodeFences::default();
+    body.lines()
+        .filter(|line| !fences.is_code(line))
+        .filter_map(|line| line.strip_prefix("# "))
+        .map(|title| title.trim().trim_end_matches('#').trim_end())
+        .find(|title| !title.is_empty())
+        .map(str::to_string)
+}
+
+fn scalar_to_string(value: Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Accepts both lists and comma separated strings, as used by different note apps.
+fn string_list(value: Value) -> Vec<String> {
+    match value {
+        Value::Array(values) => values.into_iter().filter_map(scalar_to_string).collect(),
+        Value::String(s) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect(),
+        other => scalar_to_string(other).into_iter().collect(),
+    }
+}
+
+/// Converts TOML to JSON, keeping dates as RFC 3339 strings.
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(values) => values.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_frontmatter_is_parsed_and_removed() {
+        let content = "---\ntitle: Weekly review\ntags: [work, \"#planning\"]\naliases: review\ndate: 2024-05-01\npriority: 2\n---\n# Heading\nBody\n";
+        let note = MarkdownNote::parse(Path::new("notes/review.md"), content);
+
+        assert_eq!(note.title, "Weekly review");
+        assert_eq!(note.frontmatter.tags, vec!["work", "planning"]);
+        assert_eq!(note.frontmatter.aliases, vec!["review"]);
+        assert_eq!(note.frontmatter.date.as_deref(), Some("2024-05-01"));
+        assert_eq!(
+            note.frontmatter.extra.get("priority"),
+            Some(&Value::from(2))
+        );
+        assert_eq!(note.body, "# Heading\nBody\n");
+        assert_eq!(&content[note.body_offset..], note.body);
+    }
+
+    #[test]
+    fn test_toml_frontmatter_is_parsed() {
+        let content = "+++\ntags = \"rust, cli\"\ndate = 2024-05-01T10:00:00Z\n[project]\nname = \"raggy\"\n+++\nBody\n";
+        let note = MarkdownNote::parse(Path::new("notes/cli.md"), content);
+
+        assert_eq!(note.title, "cli");
+        assert_eq!(note.frontmatter.tags, vec!["rust", "cli"]);
+        assert_eq!(
+            note.frontmatter.date.as_deref(),
+            Some("2024-05-01T10:00:00Z")
+        );
+        assert_eq!(
+            note.frontmatter.extra.get("project"),
+            Some(&serde_json::json!({ "name": "raggy" }))
+        );
+        assert_eq!(note.body, "Body\n");
+    }
+
+    #[test]
+    fn test_title_falls_back_to_first_h1() {
+        let note = MarkdownNote::parse(Path::new("notes/x.md"), "Intro\n# Real title\n");
+
+        assert_eq!(note.title, "Real title");
+        assert_eq!(note.frontmatter, Frontmatter::default());
+        assert_eq!(note.body_offset, 0);
+    }
+
+    #[test]
+    fn test_title_skips_comments_in_code() {
+        let content = "```bash\n# restart the gateway\nsudo reboot\n```\n# VPN\n";
+        let note = MarkdownNote::parse(Path::new("notes/vpn-notes.md"), content);
+        assert_eq!(note.title, "VPN");
+
+        let note = MarkdownNote::parse(Path::new("notes/vpn-notes.md"), "~~~\n# TODO\n~~~\n");
+        assert_eq!(note.title, "vpn-notes");
+    }
+
+    #[test]
+    fn test_unterminated_block_is_body() {
+        let content = "---\nnot frontmatter\n";
+        let note = MarkdownNote::parse(Path::new("notes/rule.md"), content);
+
+        assert_eq!(note.title, "rule");
+        assert_eq!(note.body, content);
+    }
+}
@@ -0,0 +1,190 @@
+// src/rag/hybrid.rs
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Name of the sparse keyword vector stored next to the dense embedding
+pub const KEYWORD_VECTOR_NAME: &str = "keywords";
+
+/// BM25 term frequency saturation
+const BM25_K1: f32 = 1.2;
+/// BM25 document length normalisation
+const BM25_B: f32 = 0.75;
+/// Assumed average chunk length in tokens, as the real one is not known when indexing
+const BM25_AVG_DOC_LEN: f32 = 256.0;
+
+/// Rank offset of reciprocal rank fusion; dampens the weight of the very first ranks
+const RRF_K: f32 = 60.0;
+
+/// A sparse vector of hashed terms and their weights.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SparseVector {
+    pub indices: Vec<u32>,
+    pub values: Vec<f32>,
+}
+
+impl SparseVector {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+/// Builds the BM25 term weights of a passage. The inverse document frequency is
+/// applied at search time by the vector store.
+pub fn document_keywords(text: &str) -> SparseVector {
+    let tokens = tokenize(text);
+    let doc_len = tokens.len() as f32;
+
+    let mut term_frequencies: HashMap<u32, f32> = HashMap::new();
+    for token in &tokens {
+        *term_frequencies.entry(term_index(token)).or_default() += 1.0;
+    }
+
+    let length_norm = 1.0 - BM25_B + BM25_B * doc_len / BM25_AVG_DOC_LEN;
+    to_sparse_vector(term_frequencies.into_iter().map(|(index, tf)| {
+        let weight = tf * (BM25_K1 + 1.0) / (tf + BM25_K1 * length_norm);
+        (index, weight)
+    }))
+}
+
+/// Builds the keyword vector of a search query, weighting every distinct term equally.
+pub fn query_keywords(text: &str) -> SparseVector {
+    let terms: HashMap<u32, f32> = tokenize(text)
+        .iter()
+        .map(|token| (term_index(token), 1.0))
+        .collect();
+
+    to_sparse_vector(terms)
+}
+
+/// Splits text into lowercase terms. Identifiers such as `PROJ-1234` or `db-01.prod`
+/// are kept whole and also split into their parts, so both forms can match.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+
+    let words = text
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .map(|word| word.trim_matches(|c: char| matches!(c, '-' | '_' | '.')))
+        .filter(|word| !word.is_empty());
+
+    for word in words {
+        let word = word.to_lowercase();
+        let parts: Vec<&str> = word
+            .split(['-', '_', '.'])
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        if parts.len() > 1 {
+            tokens.extend(parts.iter().filter(|p| is_term(p)).map(|p| p.to_string()));
+        }
+        if is_term(&word) {
+            tokens.push(word);
+        }
+    }
+
+    tokens
+}
+
+fn is_term(token: &str) -> bool {
+    token.chars().count() > 1 || token.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Hashes a term into the sparse vector dimension with 32-bit FNV-1a, which is stable
+/// across builds and platforms.
+fn term_index(term: &str) -> u32 {
+    term.bytes().fold(0x811c9dc5, |hash: u32, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    })
+}
+
+fn to_sparse_vector(terms: impl IntoIterator<Item = (u32, f32)>) -> SparseVector {
+    let mut terms: Vec<(u32, f32)> = terms.into_iter().collect();
+    terms.sort_by_key(|(index, _)| *index);
+
+    SparseVector {
+        indices: terms.iter().map(|(index, _)| *index).collect(),
+        values: terms.iter().map(|(_, value)| *value).collect(),
+    }
+}
+
+/// Merges two rankings with weighted reciprocal rank fusion.
+///
+/// `keyword_weight` is the share of the keyword ranking, between 0 and 1; the dense
+/// ranking gets the rest. Returns ids with fused scores normalised so that an item
+/// ranked first in both lists scores 1, best first.
+pub fn reciprocal_rank_fusion(
+    dense_ranking: &[String],
+    keyword_ranking: &[String],
+    keyword_weight: f32,
+) -> Vec<(String, f32)> {
+    let keyword_weight = keyword_weight.clamp(0.0, 1.0);
+    let mut scores: HashMap<&str, f32> = HashMap::new();
+
+    let rankings = [
+        (dense_ranking, 1.0 - keyword_weight),
+        (keyword_ranking, keyword_weight),
+    ];
+    for (ranking, weight) in rankings {
+        for (rank, id) in ranking.iter().enumerate() {
+            *scores.entry(id.as_str()).or_default() += weight / (RRF_K + rank as f32 + 1.0);
+        }
+    }
+
+    let best_possible = 1.0 / (RRF_K + 1.0);
+    let mut fused: Vec<(String, f32)> = scores
+        .into_iter()
+        .map(|(id, score)| (id.to_string(), score / best_possible))
+        .collect();
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_keeps_identifiers_and_their_parts() {
+        assert_eq!(
+            tokenize("Deploy PROJ-1234 to db-01.prod, a b!"),
+            vec![
+                "deploy",
+                "proj",
+                "1234",
+                "proj-1234",
+                "to",
+                "db",
+                "01",
+                "prod",
+                "db-01.prod"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_document_keywords_are_sorted_and_weighted_by_frequency() {
+        let vector = document_keywords("vpn vpn gateway");
+
+        assert_eq!(vector.indices.len(), 2);
+        assert!(vector.indices.windows(2).all(|w| w[0] < w[1]));
+
+        let vpn = vector.indices.iter().position(|&i| i == term_index("vpn"));
+        let gateway = vector
+            .indices
+            .iter()
+            .position(|&i| i == term_index("gateway"));
+        assert!(vector.values[vpn.unwrap()] > vector.values[gateway.unwrap()]);
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion_respects_weight() {
+        let dense = vec!["a".to_string(), "b".to_string()];
+        let keywords = vec!["b".to_string(), "c".to_string()];
+
+        let balanced = reciprocal_rank_fusion(&dense, &keywords, 0.5);
+        assert_eq!(balanced[0].0, "b");
+
+        let dense_only = reciprocal_rank_fusion(&dense, &keywords, 0.0);
+        assert_eq!(dense_only[0], ("a".to_string(), 1.0));
+    }
+}
@@ -1,2 +1,8 @@
+pub mod chunker;
 pub mod files;
+pub mod filters;
+pub mod frontmatter;
+pub mod hybrid;
+pub mod store;
 pub mod vectors;
+pub mod watcher;
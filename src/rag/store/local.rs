@@ -0,0 +1,516 @@
+// src/rag/store/local.rs
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use async_trait::async_trait;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::rag::filters::SearchFilter;
+use crate::rag::hybrid::SparseVector;
+use crate::rag::store::{EmbeddingSpec, VectorStore};
+use crate::rag::vectors::{NotePayload, NoteVector, ScoredNote};
+
+/// Version of the file format, bumped on incompatible changes
+const LOCAL_STORE_VERSION: u32 = 1;
+
+/// Stores note vectors in a single file and searches them by brute force, which is fast
+/// enough for a personal knowledge base and needs no server.
+///
+/// Changes are kept in memory until `flush` writes the whole index back to disk. Several
+/// processes (say the TUI and `watch`) may use the same index: `flush` reloads the file
+/// under a lock and replays the changes of this process on top of it, so that those
+/// saved by others in the meantime are kept. Each process only sees the changes of the
+/// others once it flushes its own or reopens the index.
+pub struct LocalStore {
+    path: PathBuf,
+    index: RwLock<LocalIndex>,
+    /// Changes not written to disk yet, in the order they were made
+    pending: Mutex<Vec<Change>>,
+}
+
+/// A change to the index, replayed on the saved index when flushing
+#[derive(Debug, Clone)]
+enum Change {
+    Upsert(Vec<(String, LocalPoint)>),
+    DeleteFile(String),
+    Clear,
+    SetEmbedding(EmbeddingSpec),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LocalIndex {
+    version: u32,
+    /// Embedding model of the points, missing in indexes of older versions
+    #[serde(default)]
+    embedding: Option<EmbeddingSpec>,
+    /// Points keyed by their ID
+    points: BTreeMap<String, LocalPoint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LocalPoint {
+    embedding: Vec<f32>,
+    keywords: SparseVector,
+    payload: NotePayload,
+}
+
+impl Default for LocalIndex {
+    fn default() -> Self {
+        Self {
+            version: LOCAL_STORE_VERSION,
+            embedding: None,
+            points: BTreeMap::new(),
+        }
+    }
+}
+
+impl LocalIndex {
+    /// Reads the index saved at `path`, or an empty one if the file doesn't exist yet.
+    /// Saving replaces the file at once, so it is never read half written.
+    fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let index: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if index.version != LOCAL_STORE_VERSION {
+            return Err(AppError::VectorDBError(format!(
+                "Unsupported local index version {} in {}",
+                index.version,
+                path.display()
+            )));
+        }
+        Ok(index)
+    }
+
+    fn apply(&mut self, change: Change) {
+        match change {
+            Change::Upsert(points) => self.points.extend(points),
+            Change::DeleteFile(file_path) => self
+                .points
+                .retain(|_, point| point.payload.file_path != file_path),
+            Change::Clear => {
+                self.points.clear();
+                self.embedding = None;
+            }
+            Change::SetEmbedding(spec) => self.embedding = Some(spec),
+        }
+    }
+}
+
+impl LocalStore {
+    /// Opens the index stored at `path`, starting empty if the file doesn't exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let index = LocalIndex::load(&path)?;
+
+        Ok(Self {
+            path,
+            index: RwLock::new(index),
+            pending: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn read(&self) -> Result<std::sync::RwLockReadGuard<'_, LocalIndex>> {
+        self.index
+            .read()
+            .map_err(|_| AppError::VectorDBError("Local index lock poisoned".into()))
+    }
+
+    fn write(&self) -> Result<std::sync::RwLockWriteGuard<'_, LocalIndex>> {
+        self.index
+            .write()
+            .map_err(|_| AppError::VectorDBError("Local index lock poisoned".into()))
+    }
+
+    fn pending(&self) -> Result<std::sync::MutexGuard<'_, Vec<Change>>> {
+        self.pending
+            .lock()
+            .map_err(|_| AppError::VectorDBError("Local index lock poisoned".into()))
+    }
+
+    /// Applies a change in memory and records it for the next `flush`.
+    fn change(&self, change: Change) -> Result<()> {
+        let mut index = self.write()?;
+        index.apply(change.clone());
+        self.pending()?.push(change);
+        Ok(())
+    }
+
+    /// Scores every point matching the filter and returns the best `limit` ones.
+    fn top_matches(
+        &self,
+        limit: u64,
+        filter: &SearchFilter,
+        score: impl Fn(&LocalPoint) -> f32,
+    ) -> Result<Vec<ScoredNote>> {
+        let index = self.read()?;
+
+        let mut matches: Vec<(&String, &LocalPoint, f32)> = index
+            .points
+            .iter()
+            .filter(|(_, point)| filter.matches(&point.payload))
+            .map(|(id, point)| (id, point, score(point)))
+            .filter(|(_, _, score)| *score > 0.0)
+            .collect();
+        matches.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+        Ok(matches
+            .into_iter()
+            .take(limit as usize)
+            .map(|(id, point, score)| ScoredNote {
+                id: id.clone(),
+                score,
+                similarity: None,
+                payload: point.payload.clone(),
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl VectorStore for LocalStore {
+    async fn test_connection(&self) -> Result<()> {
+        info!(
+            "Using local index {} with {} points",
+            self.path.display(),
+            self.read()?.points.len()
+        );
+        Ok(())
+    }
+
+    async fn initialize_collection(&self, spec: &EmbeddingSpec) -> Result<EmbeddingSpec> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if let Some(existing) = &self.read()?.embedding {
+            return Ok(existing.clone());
+        }
+
+        let dimension = self
+            .read()?
+            .points
+            .values()
+            .next()
+            .map_or(spec.dimension, |point| point.embedding.len());
+        if dimension != spec.dimension {
+            return Ok(EmbeddingSpec {
+                model: String::new(),
+                dimension,
+            });
+        }
+        self.change(Change::SetEmbedding(spec.clone()))?;
+        Ok(spec.clone())
+    }
+
+    async fn delete_collection(&self) -> Result<()> {
+        self.change(Change::Clear)
+    }
+
+    fn supports_keywords(&self) -> bool {
+        true
+    }
+
+    async fn upsert(&self, note_vectors: &[NoteVector]) -> Result<()> {
+        let points = note_vectors
+            .iter()
+            .filter_map(|note_vector| {
+                let embedding = note_vector.embedding.first()?;
+                let point = LocalPoint {
+                    embedding: embedding.clone(),
+                    keywords: note_vector.keywords.clone(),
+                    payload: note_vector.payload.clone(),
+                };
+                Some((note_vector.id.clone(), point))
+            })
+            .collect();
+        self.change(Change::Upsert(points))
+    }
+
+    async fn delete_file(&self, file_path: &str) -> Result<()> {
+        self.change(Change::DeleteFile(file_path.to_string()))
+    }
+
+    async fn search(
+        &self,
+        embedding: &[f32],
+        limit: u64,
+        filter: &SearchFilter,
+    ) -> Result<Vec<ScoredNote>> {
+        let mut notes = self.top_matches(limit, filter, |point| {
+            cosine_similarity(embedding, &point.embedding)
+        })?;
+        for note in &mut notes {
+            note.similarity = Some(note.score);
+        }
+        Ok(notes)
+    }
+
+    async fn search_keywords(
+        &self,
+        keywords: &SparseVector,
+        limit: u64,
+        filter: &SearchFilter,
+    ) -> Result<Vec<ScoredNote>> {
+        let idf = {
+            let index = self.read()?;
+            inverse_document_frequencies(keywords, index.points.values())
+        };
+
+        self.top_matches(limit, filter, |point| {
+            keywords
+                .indices
+                .iter()
+                .zip(&keywords.values)
+                .filter_map(|(term, query_weight)| {
+                    let position = point.keywords.indices.binary_search(term).ok()?;
+                    Some(idf[term] * query_weight * point.keywords.values[position])
+                })
+                .sum()
+        })
+    }
+
+    async fn scroll(&self, file_path: Option<&str>) -> Result<Vec<NotePayload>> {
+        Ok(self
+            .read()?
+            .points
+            .values()
+            .filter(|point| file_path.is_none_or(|path| point.payload.file_path == path))
+            .map(|point| point.payload.clone())
+            .collect())
+    }
+
+    async fn note_hashes(&self, file_path: Option<&str>) -> Result<HashMap<String, String>> {
+        Ok(self
+            .read()?
+            .points
+            .values()
+            .filter(|point| file_path.is_none_or(|path| point.payload.file_path == path))
+            .map(|point| {
+                (
+                    point.payload.file_path.clone(),
+                    point.payload.content_hash.clone(),
+                )
+            })
+            .collect())
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let mut index = self.write()?;
+        let mut pending = self.pending()?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        // Keep other processes from saving the index until this one is done, and start
+        // from what they saved so far
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let lock = File::create(self.path.with_extension("json.lock"))?;
+        lock.lock()?;
+        let mut saved = LocalIndex::load(&self.path)?;
+        for change in pending.iter() {
+            saved.apply(change.clone());
+        }
+        let serialized = serde_json::to_vec(&saved)?;
+
+        // Write to a temporary file first so that a crash never leaves a truncated index
+        let temporary_path = self.path.with_extension("json.tmp");
+        fs::write(&temporary_path, serialized)?;
+        fs::rename(&temporary_path, &self.path)?;
+        drop(lock);
+
+        *index = saved;
+        pending.clear();
+        info!("Saved local index to {}", self.path.display());
+        Ok(())
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// Computes the BM25 inverse document frequency of every query term, the same way
+/// Qdrant does for sparse vectors with the IDF modifier.
+fn inverse_document_frequencies<'a>(
+    keywords: &SparseVector,
+    points: impl Iterator<Item = &'a LocalPoint>,
+) -> HashMap<u32, f32> {
+    let mut document_count = 0.0;
+    let mut document_frequencies: HashMap<u32, f32> =
+        keywords.indices.iter().map(|term| (*term, 0.0)).collect();
+
+    for point in points {
+        document_count += 1.0;
+        for (term, frequency) in document_frequencies.iter_mut() {
+            if point.keywords.indices.binary_search(term).is_ok() {
+                *frequency += 1.0;
+            }
+        }
+    }
+
+    document_frequencies
+        .into_iter()
+        .map(|(term, frequency)| {
+            let idf = (1.0 + (document_count - frequency + 0.5) / (frequency + 0.5)).ln();
+            (term, idf)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::rag::chunker::NoteChunk;
+    use crate::rag::frontmatter::MarkdownNote;
+    use crate::rag::hybrid::query_keywords;
+    use crate::rag::vectors::NoteFile;
+
+    fn note_vector(file_path: &str, text: &str, embedding: Vec<f32>) -> NoteVector {
+        let note = MarkdownNote::parse(Path::new(file_path), text);
+        let note_file = NoteFile {
+            path: PathBuf::from(file_path),
+            content_hash: "hash".to_string(),
+            folders: vec![],
+            modified_at: 0,
+        };
+        let chunk = NoteChunk {
+            index: 0,
+            heading_path: vec![],
+            byte_range: 0..text.len(),
+            text: text.to_string(),
+        };
+        NoteVector::new(&note, &note_file, chunk, vec![embedding])
+    }
+
+    #[tokio::test]
+    async fn test_local_store_searches_and_persists() {
+        let dir = TempDir::new("store").unwrap();
+        let path = dir.path().join("vectors.json");
+
+        let store = LocalStore::open(&path).unwrap();
+        store
+            .upsert(&[
+                note_vector("/notes/vpn.md", "Reset the VPN gateway", vec![1.0, 0.0]),
+                note_vector(
+                    "/notes/db.md",
+                    "Ticket PROJ-1234 about db-01",
+                    vec![0.0, 1.0],
+                ),
+            ])
+            .await
+            .unwrap();
+
+        let filter = SearchFilter::default();
+        let dense = store.search(&[0.9, 0.1], 10, &filter).await.unwrap();
+        assert_eq!(dense[0].payload.file_path, "/notes/vpn.md");
+
+        let keywords = query_keywords("proj-1234");
+        let sparse = store.search_keywords(&keywords, 10, &filter).await.unwrap();
+        assert_eq!(sparse.len(), 1);
+        assert_eq!(sparse[0].payload.file_path, "/notes/db.md");
+
+        let hashes = store.note_hashes(Some("/notes/vpn.md")).await.unwrap();
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(hashes["/notes/vpn.md"], "hash");
+
+        store.delete_file("/notes/vpn.md").await.unwrap();
+        store.flush().await.unwrap();
+
+        let reopened = LocalStore::open(&path).unwrap();
+        let payloads = reopened.scroll(None).await.unwrap();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].file_path, "/notes/db.md");
+    }
+
+    #[tokio::test]
+    async fn test_local_store_keeps_writes_of_other_processes() {
+        let dir = TempDir::new("store").unwrap();
+        let path = dir.path().join("vectors.json");
+
+        // Say the TUI and `watch`, both opened before either saved
+        let first = LocalStore::open(&path).unwrap();
+        let second = LocalStore::open(&path).unwrap();
+        first
+            .upsert(&[note_vector("/notes/vpn.md", "VPN", vec![1.0, 0.0])])
+            .await
+            .unwrap();
+        first.flush().await.unwrap();
+        second
+            .upsert(&[note_vector("/notes/db.md", "db-01", vec![0.0, 1.0])])
+            .await
+            .unwrap();
+        second.delete_file("/notes/todo.md").await.unwrap();
+        second.flush().await.unwrap();
+
+        // The second one also sees the note of the first after saving
+        assert_eq!(second.scroll(None).await.unwrap().len(), 2);
+        let reopened = LocalStore::open(&path).unwrap();
+        let mut paths: Vec<_> = reopened
+            .scroll(None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|payload| payload.file_path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, ["/notes/db.md", "/notes/vpn.md"]);
+    }
+
+    #[tokio::test]
+    async fn test_local_store_records_embedding_model() {
+        let dir = TempDir::new("store").unwrap();
+        let path = dir.path().join("vectors.json");
+        let small = EmbeddingSpec {
+            model: "tiny-embed".to_string(),
+            dimension: 2,
+        };
+        let large = EmbeddingSpec {
+            model: "mxbai-embed-large".to_string(),
+            dimension: 1024,
+        };
+
+        // Indexes of older versions don't record the model
+        let store = LocalStore::open(&path).unwrap();
+        store
+            .upsert(&[note_vector("/notes/vpn.md", "VPN", vec![1.0, 0.0])])
+            .await
+            .unwrap();
+        let unknown = store.initialize_collection(&large).await.unwrap();
+        assert_eq!(unknown.model, "");
+        assert_eq!(unknown.dimension, 2);
+
+        assert_eq!(store.initialize_collection(&small).await.unwrap(), small);
+        store.flush().await.unwrap();
+
+        let reopened = LocalStore::open(&path).unwrap();
+        assert_eq!(reopened.initialize_collection(&large).await.unwrap(), small);
+
+        reopened.delete_collection().await.unwrap();
+        assert_eq!(reopened.initialize_collection(&large).await.unwrap(), large);
+        assert!(reopened.scroll(None).await.unwrap().is_empty());
+    }
+}
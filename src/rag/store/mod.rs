@@ -0,0 +1,89 @@
+// src/rag/store/mod.rs
+use std::collections::HashMap;
+use std::fmt;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::rag::filters::SearchFilter;
+use crate::rag::hybrid::SparseVector;
+use crate::rag::vectors::{NotePayload, NoteVector, ScoredNote};
+
+pub mod local;
+pub mod qdrant;
+
+/// Embedding model the vectors of a collection were created with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddingSpec {
+    /// Name of the model; empty for collections of older versions that didn't record it
+    pub model: String,
+    /// Number of dimensions of the model's vectors
+    pub dimension: usize,
+}
+
+impl fmt::Display for EmbeddingSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.model.is_empty() {
+            write!(f, "an unknown model ({} dimensions)", self.dimension)
+        } else {
+            write!(f, "'{}' ({} dimensions)", self.model, self.dimension)
+        }
+    }
+}
+
+/// Storage backend for note chunk vectors and their payloads.
+///
+/// Implementations only store and retrieve points; chunking, hashing and rank fusion
+/// are done by `VectorDB` on top of them.
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// Checks that the backend is reachable.
+    async fn test_connection(&self) -> Result<()>;
+
+    /// Creates the notes collection and its indexes for `spec` if they don't exist yet.
+    ///
+    /// Returns the embedding model the collection is for. Existing collections that didn't
+    /// record one are assigned `spec` if their vectors have its dimension.
+    async fn initialize_collection(&self, spec: &EmbeddingSpec) -> Result<EmbeddingSpec>;
+
+    /// Deletes the notes collection with all its points.
+    async fn delete_collection(&self) -> Result<()>;
+
+    /// Whether the collection can be searched by keyword vectors.
+    fn supports_keywords(&self) -> bool;
+
+    /// Inserts the given chunks, overwriting points with the same ID.
+    async fn upsert(&self, note_vectors: &[NoteVector]) -> Result<()>;
+
+    /// Deletes every point that was created from the given note file.
+    async fn delete_file(&self, file_path: &str) -> Result<()>;
+
+    /// Returns the chunks closest to the query embedding, best first.
+    async fn search(
+        &self,
+        embedding: &[f32],
+        limit: u64,
+        filter: &SearchFilter,
+    ) -> Result<Vec<ScoredNote>>;
+
+    /// Returns the chunks with the best keyword matches, best first.
+    async fn search_keywords(
+        &self,
+        keywords: &SparseVector,
+        limit: u64,
+        filter: &SearchFilter,
+    ) -> Result<Vec<ScoredNote>>;
+
+    /// Returns the payloads of all stored chunks, or only those of one note file.
+    async fn scroll(&self, file_path: Option<&str>) -> Result<Vec<NotePayload>>;
+
+    /// Returns the content hash of every stored note, or only of one note file, keyed
+    /// by file path. Only fetches the fields it needs, as it runs on every indexing pass.
+    async fn note_hashes(&self, file_path: Option<&str>) -> Result<HashMap<String, String>>;
+
+    /// Persists pending changes, for backends that buffer writes.
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
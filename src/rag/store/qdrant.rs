@@ -0,0 +1,495 @@
+// src/rag/store/qdrant.rs
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use async_trait::async_trait;
+use log::{info, warn};
+use qdrant_client::qdrant::point_id::PointIdOptions;
+use qdrant_client::qdrant::{
+    Condition, CreateCollectionBuilder, CreateFieldIndexCollectionBuilder, DeletePointsBuilder,
+    Distance, FieldType, Filter, GetPointsBuilder, Modifier, NamedVectors, PayloadIncludeSelector,
+    PointId, PointStruct, ScoredPoint, ScrollPointsBuilder, SearchPoints, SparseIndices,
+    SparseVectorParamsBuilder, SparseVectorsConfigBuilder, UpsertPointsBuilder, Value, Vector,
+    VectorParams, VectorsConfig, WithPayloadSelector, WithVectorsSelector,
+};
+use qdrant_client::{Payload, Qdrant};
+
+use crate::prelude::*;
+use crate::rag::filters::SearchFilter;
+use crate::rag::hybrid::{SparseVector, KEYWORD_VECTOR_NAME};
+use crate::rag::store::{EmbeddingSpec, VectorStore};
+use crate::rag::vectors::{NotePayload, NoteVector, ScoredNote};
+
+/// Number of points fetched per page when scrolling through the collection
+const SCROLL_PAGE_SIZE: u32 = 1000;
+
+/// ID of the point recording the embedding model of the collection. Qdrant has no
+/// collection metadata, so it is kept in a point that searches and scrolls exclude.
+const METADATA_POINT_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+/// Stores note vectors in a Qdrant server.
+pub struct QdrantStore {
+    pub client: Qdrant,
+    collection: String,
+    /// Whether the collection has a keyword vector; collections created before hybrid
+    /// search was added only support dense search
+    keyword_search: AtomicBool,
+}
+
+impl QdrantStore {
+    pub fn new(url: &str, collection: &str) -> Result<Self> {
+        let client = Qdrant::from_url(url).build()?;
+
+        Ok(Self {
+            client,
+            collection: collection.to_string(),
+            keyword_search: AtomicBool::new(false),
+        })
+    }
+
+    /// Checks whether an existing collection was created with the keyword vector.
+    async fn detect_keyword_vector(&self) -> Result<()> {
+        let info = self.client.collection_info(&self.collection).await?;
+
+        let has_keywords = info
+            .result
+            .and_then(|info| info.config)
+            .and_then(|config| config.params)
+            .and_then(|params| params.sparse_vectors_config)
+            .is_some_and(|sparse| sparse.map.contains_key(KEYWORD_VECTOR_NAME));
+
+        if !has_keywords {
+            warn!(
+                "Collection '{}' has no keyword vector, falling back to vector-only search. \
+                 Delete the collection and re-index to enable hybrid search.",
+                self.collection
+            );
+        }
+        self.keyword_search.store(has_keywords, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Reads the embedding model recorded in the collection, recording `spec` for
+    /// collections of older versions whose vectors have its dimension.
+    async fn stored_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<EmbeddingSpec> {
+        let response = self
+            .client
+            .get_points(
+                GetPointsBuilder::new(&self.collection, vec![METADATA_POINT_ID.into()])
+                    .with_payload(true),
+            )
+            .await?;
+        if let Some(point) = response.result.into_iter().next() {
+            let json = serde_json::Value::from(Payload::from(point.payload));
+            return Ok(serde_json::from_value(json)?);
+        }
+
+        let info = self.client.collection_info(&self.collection).await?;
+        let dimension = info
+            .result
+            .and_then(|info| info.config)
+            .and_then(|config| config.params)
+            .and_then(|params| params.vectors_config)
+            .and_then(|vectors| vectors.config)
+            .and_then(|config| match config {
+                qdrant_client::qdrant::vectors_config::Config::Params(params) => {
+                    Some(params.size as usize)
+                }
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        if dimension != spec.dimension {
+            return Ok(EmbeddingSpec {
+                model: String::new(),
+                dimension,
+            });
+        }
+        info!(
+            "Recording embedding model {} for collection '{}'",
+            spec, self.collection
+        );
+        self.record_embedding_spec(spec).await?;
+        Ok(spec.clone())
+    }
+
+    async fn record_embedding_spec(&self, spec: &EmbeddingSpec) -> Result<()> {
+        let payload = Payload::try_from(serde_json::to_value(spec)?)
+            .map_err(|e| AppError::VectorDBError(format!("Invalid collection metadata: {}", e)))?;
+        // The dense vector is required; a unit vector keeps cosine similarity defined
+        let mut vector = vec![0.0; spec.dimension];
+        vector[0] = 1.0;
+
+        let point = PointStruct::new(METADATA_POINT_ID, vector, payload);
+        self.client
+            .upsert_points(UpsertPointsBuilder::new(&self.collection, vec![point]).wait(true))
+            .await?;
+        Ok(())
+    }
+
+    /// Creates the payload indexes used for filtering; Qdrant ignores indexes that already exist.
+    async fn create_payload_indexes(&self) -> Result<()> {
+        let indexes = [
+            ("file_path", FieldType::Keyword),
+            ("tags", FieldType::Keyword),
+            ("folders", FieldType::Keyword),
+            ("modified_at", FieldType::Integer),
+        ];
+
+        for (field_name, field_type) in indexes {
+            self.client
+                .create_field_index(
+                    CreateFieldIndexCollectionBuilder::new(
+                        &self.collection,
+                        field_name,
+                        field_type,
+                    )
+                    .wait(true),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VectorStore for QdrantStore {
+    async fn test_connection(&self) -> Result<()> {
+        let collections_response = self.client.list_collections().await?;
+        info!(
+            "Connected to Qdrant. Found {} collections",
+            collections_response.collections.len()
+        );
+
+        // Keyword search works before the collection is initialized, e.g. while the LLM
+        // provider is unreachable
+        if collections_response
+            .collections
+            .iter()
+            .any(|collection| collection.name == self.collection)
+        {
+            self.detect_keyword_vector().await?;
+        }
+        Ok(())
+    }
+
+    async fn initialize_collection(&self, spec: &EmbeddingSpec) -> Result<EmbeddingSpec> {
+        if self.client.collection_exists(&self.collection).await? {
+            info!("Collection '{}' already exists", self.collection);
+            self.detect_keyword_vector().await?;
+            self.create_payload_indexes().await?;
+            return self.stored_embedding_spec(spec).await;
+        }
+
+        // Create collection with the appropriate vector size for our embeddings, and a
+        // sparse keyword vector weighted by inverse document frequency
+        let mut keyword_vectors_config = SparseVectorsConfigBuilder::default();
+        keyword_vectors_config.add_named_vector_params(
+            KEYWORD_VECTOR_NAME,
+            SparseVectorParamsBuilder::default().modifier(Modifier::Idf),
+        );
+
+        let create_collection = CreateCollectionBuilder::new(self.collection.clone())
+            .vectors_config(VectorsConfig {
+                config: Some(qdrant_client::qdrant::vectors_config::Config::Params(
+                    VectorParams {
+                        size: spec.dimension as u64,
+                        distance: Distance::Cosine.into(),
+                        on_disk: Some(false),
+                        hnsw_config: None,
+                        quantization_config: None,
+                        datatype: None,
+                        multivector_config: None,
+                    },
+                )),
+            })
+            .sparse_vectors_config(keyword_vectors_config)
+            .build();
+
+        self.client.create_collection(create_collection).await?;
+        self.keyword_search.store(true, Ordering::Relaxed);
+        self.create_payload_indexes().await?;
+        self.record_embedding_spec(spec).await?;
+        info!(
+            "Successfully initialized collection '{}' for {}",
+            self.collection, spec
+        );
+        Ok(spec.clone())
+    }
+
+    async fn delete_collection(&self) -> Result<()> {
+        if self.client.collection_exists(&self.collection).await? {
+            self.client.delete_collection(&self.collection).await?;
+            info!("Deleted collection '{}'", self.collection);
+        }
+        Ok(())
+    }
+
+    fn supports_keywords(&self) -> bool {
+        self.keyword_search.load(Ordering::Relaxed)
+    }
+
+    async fn upsert(&self, note_vectors: &[NoteVector]) -> Result<()> {
+        let with_keywords = self.supports_keywords();
+        let points: Vec<PointStruct> = note_vectors
+            .iter()
+            .flat_map(|note_vector| to_point_structs(note_vector, with_keywords))
+            .collect();
+        if points.is_empty() {
+            return Ok(());
+        }
+
+        let up = UpsertPointsBuilder::new(self.collection.clone(), points).wait(true);
+
+        self.client.upsert_points(up).await?;
+        Ok(())
+    }
+
+    async fn delete_file(&self, file_path: &str) -> Result<()> {
+        let delete = DeletePointsBuilder::new(&self.collection)
+            .points(file_path_filter(file_path))
+            .wait(true);
+
+        self.client.delete_points(delete).await?;
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        embedding: &[f32],
+        limit: u64,
+        filter: &SearchFilter,
+    ) -> Result<Vec<ScoredNote>> {
+        let search_result = self
+            .client
+            .search_points(SearchPoints {
+                collection_name: self.collection.clone(),
+                vector: embedding.to_vec(),
+                filter: Some(notes_filter(filter.to_qdrant())),
+                limit,
+                with_payload: Some(WithPayloadSelector {
+                    selector_options: Some(
+                        qdrant_client::qdrant::with_payload_selector::SelectorOptions::Enable(true),
+                    ),
+                }),
+                with_vectors: Some(WithVectorsSelector {
+                    selector_options: Some(
+                        qdrant_client::qdrant::with_vectors_selector::SelectorOptions::Enable(
+                            false,
+                        ),
+                    ),
+                }),
+                ..Default::default()
+            })
+            .await?;
+
+        // The collection uses the cosine distance, so scores are similarities
+        search_result
+            .result
+            .into_iter()
+            .map(|point| {
+                let mut note = to_scored_note(point)?;
+                note.similarity = Some(note.score);
+                Ok(note)
+            })
+            .collect()
+    }
+
+    async fn search_keywords(
+        &self,
+        keywords: &SparseVector,
+        limit: u64,
+        filter: &SearchFilter,
+    ) -> Result<Vec<ScoredNote>> {
+        let search_result = self
+            .client
+            .search_points(SearchPoints {
+                collection_name: self.collection.clone(),
+                vector: keywords.values.clone(),
+                sparse_indices: Some(SparseIndices {
+                    data: keywords.indices.clone(),
+                }),
+                vector_name: Some(KEYWORD_VECTOR_NAME.to_string()),
+                filter: Some(notes_filter(filter.to_qdrant())),
+                limit,
+                with_payload: Some(WithPayloadSelector {
+                    selector_options: Some(
+                        qdrant_client::qdrant::with_payload_selector::SelectorOptions::Enable(true),
+                    ),
+                }),
+                ..Default::default()
+            })
+            .await?;
+
+        search_result
+            .result
+            .into_iter()
+            .map(to_scored_note)
+            .collect()
+    }
+
+    async fn scroll(&self, file_path: Option<&str>) -> Result<Vec<NotePayload>> {
+        let mut payloads = Vec::new();
+        let mut offset = None;
+
+        loop {
+            let mut scroll = ScrollPointsBuilder::new(&self.collection)
+                .limit(SCROLL_PAGE_SIZE)
+                .with_payload(true)
+                .with_vectors(false)
+                .filter(notes_filter(file_path.map(file_path_filter)));
+            if let Some(offset) = offset {
+                scroll = scroll.offset(offset);
+            }
+
+            let response = self.client.scroll(scroll).await?;
+
+            for point in response.result {
+                // Skip malformed points instead of failing the whole pass
+                match payload_from_qdrant(point.payload) {
+                    Ok(payload) => payloads.push(payload),
+                    Err(e) => warn!(
+                        "Skipping point {} with an invalid payload: {}",
+                        point_id_to_string(point.id),
+                        e
+                    ),
+                }
+            }
+
+            offset = response.next_page_offset;
+            if offset.is_none() {
+                break;
+            }
+        }
+
+        Ok(payloads)
+    }
+
+    async fn note_hashes(&self, file_path: Option<&str>) -> Result<HashMap<String, String>> {
+        let mut hashes = HashMap::new();
+        let mut offset = None;
+
+        loop {
+            // All chunks of a note share its hash, so one point is enough for a single note
+            let limit = if file_path.is_some() {
+                1
+            } else {
+                SCROLL_PAGE_SIZE
+            };
+            let mut scroll = ScrollPointsBuilder::new(&self.collection)
+                .limit(limit)
+                .with_payload(PayloadIncludeSelector::new(vec![
+                    "file_path".to_string(),
+                    "content_hash".to_string(),
+                ]))
+                .with_vectors(false)
+                .filter(notes_filter(file_path.map(file_path_filter)));
+            if let Some(offset) = offset {
+                scroll = scroll.offset(offset);
+            }
+
+            let response = self.client.scroll(scroll).await?;
+
+            for point in response.result {
+                let file_path = point.payload.get("file_path").and_then(|v| v.as_str());
+                let content_hash = point.payload.get("content_hash").and_then(|v| v.as_str());
+
+                if let Some(file_path) = file_path {
+                    hashes.insert(
+                        file_path.to_string(),
+                        content_hash.cloned().unwrap_or_default(),
+                    );
+                }
+            }
+
+            offset = response.next_page_offset;
+            if offset.is_none() || file_path.is_some() {
+                break;
+            }
+        }
+
+        Ok(hashes)
+    }
+}
+
+fn file_path_filter(file_path: &str) -> Filter {
+    Filter::must([Condition::matches("file_path", file_path.to_string())])
+}
+
+/// Restricts a filter to note chunks, leaving out the metadata point.
+fn notes_filter(filter: Option<Filter>) -> Filter {
+    let mut filter = filter.unwrap_or_default();
+    filter.must_not.push(Condition::has_id([METADATA_POINT_ID]));
+    filter
+}
+
+/// Deserializes a payload returned by Qdrant.
+fn payload_from_qdrant(payload: HashMap<String, Value>) -> Result<NotePayload> {
+    let json = serde_json::Value::from(Payload::from(payload));
+    Ok(serde_json::from_value(json)?)
+}
+
+fn to_scored_note(point: ScoredPoint) -> Result<ScoredNote> {
+    Ok(ScoredNote {
+        id: point_id_to_string(point.id),
+        score: point.score,
+        similarity: None,
+        payload: payload_from_qdrant(point.payload)?,
+    })
+}
+
+fn point_id_to_string(id: Option<PointId>) -> String {
+    match id.and_then(|id| id.point_id_options) {
+        Some(PointIdOptions::Num(num)) => num.to_string(),
+        Some(PointIdOptions::Uuid(uuid)) => uuid,
+        None => "Unknown ID".to_string(),
+    }
+}
+
+/// Builds the Qdrant points of a chunk, adding its keyword vector when the collection
+/// supports hybrid search.
+fn to_point_structs(note_vector: &NoteVector, with_keywords: bool) -> Vec<PointStruct> {
+    let note = &note_vector.payload;
+    let mut payload = Payload::new();
+    payload.insert("title", note.title.clone());
+    payload.insert("content", note.content.clone());
+    payload.insert("file_path", note.file_path.clone());
+    payload.insert("content_hash", note.content_hash.clone());
+    payload.insert("heading_path", note.heading_path.clone());
+    payload.insert("chunk_index", note.chunk_index as i64);
+    payload.insert("byte_start", note.byte_start as i64);
+    payload.insert("byte_end", note.byte_end as i64);
+    payload.insert("tags", note.tags.clone());
+    payload.insert("aliases", note.aliases.clone());
+    if let Some(date) = &note.date {
+        payload.insert("date", date.clone());
+    }
+    payload.insert(
+        "frontmatter",
+        serde_json::Value::Object(note.frontmatter.clone()),
+    );
+    payload.insert("folders", note.folders.clone());
+    payload.insert("modified_at", note.modified_at);
+    payload.insert("created_at", note.created_at.to_rfc3339());
+    payload.insert("updated_at", note.updated_at.to_rfc3339());
+
+    let keywords = &note_vector.keywords;
+    let mut list: Vec<PointStruct> = vec![];
+
+    for float_vector in note_vector.embedding.clone() {
+        // The dense embedding is the unnamed default vector
+        let mut vectors = NamedVectors::default().add_vector("", float_vector);
+        if with_keywords && !keywords.is_empty() {
+            vectors = vectors.add_vector(
+                KEYWORD_VECTOR_NAME,
+                Vector::new_sparse(keywords.indices.clone(), keywords.values.clone()),
+            );
+        }
+        list.push(PointStruct::new(
+            note_vector.id.clone(),
+            vectors,
+            payload.clone(),
+        ));
+    }
+    list
+}
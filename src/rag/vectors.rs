@@ -1,185 +1,441 @@
 use log::info;
-use qdrant_client::qdrant::{
-    CreateCollectionBuilder, Distance, PointStruct, SearchPoints, SearchResponse,
-    UpsertPointsBuilder, VectorParams, VectorsConfig, WithPayloadSelector, WithVectorsSelector,
-};
-use qdrant_client::{Payload, Qdrant};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::llama::consts::EMBEDDING_SIZE;
+use crate::config::app_config::{AppConfiguration, VectorStoreKind};
 use crate::prelude::*;
+use crate::rag::chunker::NoteChunk;
+use crate::rag::filters::SearchFilter;
+use crate::rag::frontmatter::MarkdownNote;
+use crate::rag::hybrid::{document_keywords, query_keywords, reciprocal_rank_fusion, SparseVector};
+use crate::rag::store::local::LocalStore;
+use crate::rag::store::qdrant::QdrantStore;
+use crate::rag::store::{EmbeddingSpec, VectorStore};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::PathBuf;
 
-pub const NOTES_QDRANT_COLLECTION_NAME: &str = "private_notes";
+/// Each retriever of a hybrid search fetches this many times the requested results, so
+/// that notes ranked low by one of them can still make it into the fused list
+const HYBRID_CANDIDATE_FACTOR: u64 = 3;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Derives a stable point ID from the note path and chunk index, so that re-indexing a
+/// note overwrites its points instead of adding new ones.
+pub fn note_point_id(file_path: &str, chunk_index: usize) -> String {
+    let key = format!("file://{}#{}", file_path, chunk_index);
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, key.as_bytes()).to_string()
+}
+
+/// File-level metadata shared by every chunk of a note
+#[derive(Debug, Clone)]
+pub struct NoteFile {
+    pub path: PathBuf,
+    pub content_hash: String,
+    /// Ancestor folders relative to the scan path, used for folder filters
+    pub folders: Vec<String>,
+    /// Last modification time as a Unix timestamp
+    pub modified_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotePayload {
     pub title: String,
     pub content: String,
     pub file_path: String,
+    /// Hash of the whole note file, used to skip unchanged notes when re-indexing
+    #[serde(default)]
+    pub content_hash: String,
+    /// Headings enclosing the chunk, outermost first
+    #[serde(default)]
+    pub heading_path: Vec<String>,
+    #[serde(default)]
+    pub chunk_index: usize,
+    /// Byte range of the chunk within the note file
+    #[serde(default)]
+    pub byte_start: usize,
+    #[serde(default)]
+    pub byte_end: usize,
+    /// Tags from the frontmatter, without a leading `#`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Date from the frontmatter, as written in the note
+    #[serde(default)]
+    pub date: Option<String>,
+    /// Custom frontmatter keys with their typed values
+    #[serde(default)]
+    pub frontmatter: serde_json::Map<String, serde_json::Value>,
+    /// Ancestor folders of the note relative to the scan path
+    #[serde(default)]
+    pub folders: Vec<String>,
+    /// Last modification time of the note file as a Unix timestamp
+    #[serde(default)]
+    pub modified_at: i64,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+impl NotePayload {
+    /// Title of the note followed by the headings of the chunk, e.g. `Note > Setup > Linux`.
+    pub fn section_label(&self) -> String {
+        let mut parts = vec![self.title.as_str()];
+        parts.extend(self.heading_path.iter().map(String::as_str));
+        parts.join(" > ")
+    }
+}
+
+/// A note chunk returned by a search, best matches first
+#[derive(Debug)]
+pub struct ScoredNote {
+    pub id: String,
+    /// Ranking score; for a hybrid search it comes from the ranks of the chunk, not
+    /// from how well it matches
+    pub score: f32,
+    /// Cosine similarity between the query and the chunk embeddings, or `None` for
+    /// chunks only found by their keywords
+    pub similarity: Option<f32>,
+    pub payload: NotePayload,
+}
+
+impl ScoredNote {
+    /// Whether the chunk is at least `min_similarity` similar to the query. Unlike the
+    /// ranking score, the similarity says how well the chunk matches in absolute terms.
+    pub fn is_similar(&self, min_similarity: f32) -> bool {
+        self.similarity
+            .is_some_and(|similarity| similarity >= min_similarity)
+    }
+}
+
 #[derive(Debug)]
 pub struct NoteVector {
     pub id: String,
     pub embedding: Vec<Vec<f32>>,
+    /// BM25 weights of the chunk's terms, used for keyword search
+    pub keywords: SparseVector,
     pub payload: NotePayload,
 }
 
 impl NoteVector {
     pub fn new(
-        title: String,
-        content: String,
-        file_path: PathBuf,
+        note: &MarkdownNote,
+        note_file: &NoteFile,
+        chunk: NoteChunk,
         embedding: Vec<Vec<f32>>,
     ) -> Self {
         let now = chrono::Utc::now();
+        let file_path = note_file.path.to_string_lossy().to_string();
+        let mut header = vec![note.title.as_str()];
+        header.extend(chunk.heading_path.iter().map(String::as_str));
+        let keywords = document_keywords(&format!("{}\n{}", header.join(" "), chunk.text));
+
         Self {
-            id: Uuid::new_v4().to_string(),
+            id: note_point_id(&file_path, chunk.index),
             embedding,
+            keywords,
             payload: NotePayload {
-                title,
-                content,
-                file_path: file_path.to_string_lossy().to_string(),
+                title: note.title.clone(),
+                content: chunk.text,
+                file_path,
+                content_hash: note_file.content_hash.clone(),
+                heading_path: chunk.heading_path,
+                chunk_index: chunk.index,
+                byte_start: chunk.byte_range.start,
+                byte_end: chunk.byte_range.end,
+                tags: note.frontmatter.tags.clone(),
+                aliases: note.frontmatter.aliases.clone(),
+                date: note.frontmatter.date.clone(),
+                frontmatter: note.frontmatter.extra.clone(),
+                folders: note_file.folders.clone(),
+                modified_at: note_file.modified_at,
                 created_at: now,
                 updated_at: now,
             },
         }
     }
+}
 
-    fn to_point_structs(&self) -> Vec<PointStruct> {
-        let mut payload = Payload::new();
-        payload.insert("title", self.payload.title.clone());
-        payload.insert("content", self.payload.content.clone());
-        payload.insert("file_path", self.payload.file_path.clone());
-        payload.insert("created_at", self.payload.created_at.to_rfc3339());
-        payload.insert("updated_at", self.payload.updated_at.to_rfc3339());
-
-        let mut list: Vec<PointStruct> = vec![];
-
-        for float_vector in self.embedding.clone() {
-            list.push(PointStruct::new(
-                self.id.clone(),
-                float_vector,
-                payload.clone(),
-            ));
-        }
-        list
-    }
+/// Size of the note index
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct IndexStats {
+    pub notes: usize,
+    pub chunks: usize,
 }
 
+/// Note index on top of a pluggable `VectorStore` backend
 pub struct VectorDB {
-    pub client: Qdrant,
+    store: Box<dyn VectorStore>,
+    /// Share of the keyword ranking in hybrid search results, between 0 and 1
+    keyword_weight: f32,
 }
 
 impl VectorDB {
-    pub fn new() -> Result<Self> {
-        let client = Qdrant::from_url("http://localhost:6334").build()?;
+    pub fn new(store: Box<dyn VectorStore>, keyword_weight: f32) -> Self {
+        Self {
+            store,
+            keyword_weight: keyword_weight.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Opens the backend selected by `AppConfiguration::vector_store`.
+    pub fn from_config(config: &AppConfiguration) -> Result<Self> {
+        let store: Box<dyn VectorStore> = match config.vector_store {
+            VectorStoreKind::Qdrant => Box::new(QdrantStore::new(
+                &config.qdrant_url,
+                &config.collection_name,
+            )?),
+            VectorStoreKind::Local => {
+                let path = AppConfiguration::local_store_path().map_err(|e| {
+                    AppError::VectorDBError(format!("Failed to locate local index: {}", e))
+                })?;
+                Box::new(LocalStore::open(path)?)
+            }
+        };
 
-        Ok(Self { client })
+        Ok(Self::new(store, config.keyword_weight))
     }
 
     pub async fn test_connection(&self) -> Result<()> {
-        let collections_response = self.client.list_collections().await?;
-        info!(
-            "Connected to Qdrant. Found {} collections",
-            collections_response.collections.len()
-        );
-        Ok(())
+        self.store.test_connection().await
     }
 
-    pub async fn initialize_collections(&self) -> Result<()> {
-        // Check if collection already exists
-        let collections_response = self.client.list_collections().await?;
-
-        if collections_response
-            .collections
-            .iter()
-            .any(|c| c.name == NOTES_QDRANT_COLLECTION_NAME)
-        {
-            info!(
-                "Collection '{}' already exists",
-                NOTES_QDRANT_COLLECTION_NAME
-            );
-            return Ok(());
+    /// Creates the notes collection for the embedding model, or checks that the existing
+    /// one holds vectors of that model.
+    pub async fn initialize_collections(&self, spec: &EmbeddingSpec) -> Result<()> {
+        let existing = self.store.initialize_collection(spec).await?;
+        if existing != *spec {
+            return Err(AppError::VectorDBError(format!(
+                "The notes were indexed with {}, but the embedding model is {}. \
+                 Run 'reindex --recreate' to rebuild the index for the new model.",
+                existing, spec
+            )));
         }
+        Ok(())
+    }
 
-        // Create collection with the appropriate vector size for our embeddings
-
-        let create_collection =
-            CreateCollectionBuilder::new(NOTES_QDRANT_COLLECTION_NAME.to_string())
-                .vectors_config(VectorsConfig {
-                    config: Some(qdrant_client::qdrant::vectors_config::Config::Params(
-                        VectorParams {
-                            size: EMBEDDING_SIZE as u64,
-                            distance: Distance::Cosine.into(),
-                            on_disk: Some(false),
-                            hnsw_config: None,
-                            quantization_config: None,
-                            datatype: None,
-                            multivector_config: None,
-                        },
-                    )),
-                })
-                .build();
+    /// Deletes all indexed notes and creates the collection anew for the embedding model.
+    pub async fn recreate_collections(&self, spec: &EmbeddingSpec) -> Result<()> {
+        self.store.delete_collection().await?;
+        self.store.initialize_collection(spec).await?;
+        self.store.flush().await
+    }
+
+    /// Replaces all points of a note with the given chunk vectors.
+    pub async fn replace_note_vectors(
+        &self,
+        file_path: &str,
+        note_vectors: Vec<NoteVector>,
+    ) -> Result<()> {
+        self.store.delete_file(file_path).await?;
+        self.store.upsert(&note_vectors).await?;
 
-        self.client.create_collection(create_collection).await?;
         info!(
-            "Successfully initialized collection '{}'",
-            NOTES_QDRANT_COLLECTION_NAME
+            "Successfully saved {} vectors for: {}",
+            note_vectors.len(),
+            file_path
         );
         Ok(())
     }
 
-    pub async fn save_note_vector(&self, note_vector: NoteVector) -> Result<()> {
-        let points = note_vector.to_point_structs();
+    /// Deletes every point that was created from the given note file.
+    pub async fn delete_note_vectors(&self, file_path: &str) -> Result<()> {
+        self.store.delete_file(file_path).await
+    }
 
-        let up = UpsertPointsBuilder::new(NOTES_QDRANT_COLLECTION_NAME.to_string(), points);
+    /// Writes pending changes to disk, for backends that buffer them.
+    pub async fn flush(&self) -> Result<()> {
+        self.store.flush().await
+    }
 
-        self.client.upsert_points(up).await?;
+    /// Returns the content hash stored for a note, or `None` if it is not indexed.
+    pub async fn indexed_note_hash(&self, file_path: &str) -> Result<Option<String>> {
+        let mut hashes = self.store.note_hashes(Some(file_path)).await?;
+        Ok(hashes.remove(file_path))
+    }
 
-        info!("Successfully saved vector with id: {}", note_vector.id);
-        Ok(())
+    /// Returns the content hash of every indexed note, keyed by file path.
+    ///
+    /// Points indexed before content hashes were stored map to an empty hash.
+    pub async fn indexed_note_hashes(&self) -> Result<HashMap<String, String>> {
+        self.store.note_hashes(None).await
     }
-    pub async fn search_similar_notes(
+
+    /// Returns the indexed chunks of a note in order, or nothing if it is not indexed.
+    pub async fn note_chunks(&self, file_path: &str) -> Result<Vec<NotePayload>> {
+        let mut chunks = self.store.scroll(Some(file_path)).await?;
+        chunks.sort_by_key(|chunk| chunk.chunk_index);
+        Ok(chunks)
+    }
+
+    /// Counts the indexed notes carrying each tag.
+    pub async fn tag_counts(&self) -> Result<BTreeMap<String, usize>> {
+        let payloads = self.store.scroll(None).await?;
+        let note_tags: HashMap<&str, &[String]> = payloads
+            .iter()
+            .map(|payload| (payload.file_path.as_str(), payload.tags.as_slice()))
+            .collect();
+
+        let mut counts = BTreeMap::new();
+        for tag in note_tags.into_values().flatten() {
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Counts the indexed notes and their chunks.
+    pub async fn stats(&self) -> Result<IndexStats> {
+        let payloads = self.store.scroll(None).await?;
+        let notes: HashSet<&str> = payloads
+            .iter()
+            .map(|payload| payload.file_path.as_str())
+            .collect();
+
+        Ok(IndexStats {
+            notes: notes.len(),
+            chunks: payloads.len(),
+        })
+    }
+
+    /// Finds the chunks matching the keywords of a query, for when the query can't be
+    /// embedded because the LLM provider is unreachable.
+    pub async fn search_keywords(
         &self,
-        query_embedding: Vec<Vec<f32>>,
+        query: &str,
         limit: u64,
-    ) -> Result<SearchResponse> {
-        // Ensure we have an embedding to work with
-        if query_embedding.is_empty() || query_embedding[0].is_empty() {
+        filter: &SearchFilter,
+    ) -> Result<Vec<ScoredNote>> {
+        if !self.store.supports_keywords() {
             return Err(AppError::VectorDBError(
-                "Empty query embedding provided".into(),
+                "The index has no keyword vectors to search".into(),
             ));
         }
 
+        let keywords = query_keywords(query);
+        if keywords.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.store.search_keywords(&keywords, limit, filter).await
+    }
+
+    /// Finds the chunks most relevant to a query, restricted by `filter`.
+    ///
+    /// Combines vector similarity of the query embedding with keyword matches of the
+    /// query text through reciprocal rank fusion, weighted by `keyword_weight`. Falls
+    /// back to vector similarity alone if the collection has no keyword vector.
+    pub async fn search_notes(
+        &self,
+        query: &str,
+        query_embedding: Vec<Vec<f32>>,
+        limit: u64,
+        filter: &SearchFilter,
+    ) -> Result<Vec<ScoredNote>> {
         // Use the first vector from the embedding array
         // This is because Ollama's embedding API returns multiple vectors but we only need one for search
-        let vector_to_search = query_embedding[0].clone();
-
-        let search_result = self
-            .client
-            .search_points(SearchPoints {
-                collection_name: NOTES_QDRANT_COLLECTION_NAME.to_string(),
-                vector: vector_to_search,
-                limit,
-                with_payload: Some(WithPayloadSelector {
-                    selector_options: Some(
-                        qdrant_client::qdrant::with_payload_selector::SelectorOptions::Enable(true),
-                    ),
-                }),
-                with_vectors: Some(WithVectorsSelector {
-                    selector_options: Some(
-                        qdrant_client::qdrant::with_vectors_selector::SelectorOptions::Enable(true),
-                    ),
-                }),
-                ..Default::default()
-            })
-            .await?;
-
-        Ok(search_result)
+        let embedding = match query_embedding.first() {
+            Some(embedding) if !embedding.is_empty() => embedding,
+            _ => {
+                return Err(AppError::VectorDBError(
+                    "Empty query embedding provided".into(),
+                ))
+            }
+        };
+
+        let keywords = query_keywords(query);
+        let hybrid =
+            self.store.supports_keywords() && self.keyword_weight > 0.0 && !keywords.is_empty();
+
+        if !hybrid {
+            return self.store.search(embedding, limit, filter).await;
+        }
+
+        let candidates = limit * HYBRID_CANDIDATE_FACTOR;
+        let (dense, sparse) = tokio::try_join!(
+            self.store.search(embedding, candidates, filter),
+            self.store.search_keywords(&keywords, candidates, filter),
+        )?;
+
+        let dense_ranking: Vec<String> = dense.iter().map(|note| note.id.clone()).collect();
+        let keyword_ranking: Vec<String> = sparse.iter().map(|note| note.id.clone()).collect();
+
+        // Dense hits come last, so that chunks found by both keep their similarity
+        let mut notes: HashMap<String, ScoredNote> = sparse
+            .into_iter()
+            .chain(dense)
+            .map(|note| (note.id.clone(), note))
+            .collect();
+
+        Ok(
+            reciprocal_rank_fusion(&dense_ranking, &keyword_ranking, self.keyword_weight)
+                .into_iter()
+                .take(limit as usize)
+                .filter_map(|(id, score)| {
+                    notes.remove(&id).map(|note| ScoredNote { score, ..note })
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_similarity_survives_fusion() {
+        let dir = TempDir::new("vectors").unwrap();
+        let store = LocalStore::open(dir.path().join("vectors.json")).unwrap();
+        let vector_db = VectorDB::new(Box::new(store), 0.5);
+
+        let note_vector = |file_path: &str, text: &str, embedding: Vec<f32>| {
+            let note = MarkdownNote::parse(Path::new(file_path), text);
+            let note_file = NoteFile {
+                path: PathBuf::from(file_path),
+                content_hash: "hash".to_string(),
+                folders: vec![],
+                modified_at: 0,
+            };
+            let chunk = NoteChunk {
+                index: 0,
+                heading_path: vec![],
+                byte_range: 0..text.len(),
+                text: text.to_string(),
+            };
+            NoteVector::new(&note, &note_file, chunk, vec![embedding])
+        };
+        for (file_path, text, embedding) in [
+            ("/notes/gateway.md", "Reset the gateway", vec![1.0, 0.0]),
+            ("/notes/vpn.md", "VPN vpn vpn links", vec![0.2, 1.0]),
+        ] {
+            vector_db
+                .replace_note_vectors(file_path, vec![note_vector(file_path, text, embedding)])
+                .await
+                .unwrap();
+        }
+
+        let hits = vector_db
+            .search_notes("vpn", vec![vec![1.0, 0.0]], 10, &SearchFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+        // The keyword match ranks first despite its low similarity
+        assert_eq!(hits[0].payload.file_path, "/notes/vpn.md");
+        assert!(hits[0].similarity < Some(0.5));
+
+        // The weak match is left out by its similarity, whatever its rank
+        let similar: Vec<_> = hits.iter().filter(|hit| hit.is_similar(0.5)).collect();
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].payload.file_path, "/notes/gateway.md");
+    }
+
+    #[test]
+    fn test_note_point_id_is_stable_per_chunk() {
+        let first = note_point_id("/notes/a.md", 0);
+
+        assert_eq!(first, note_point_id("/notes/a.md", 0));
+        assert_ne!(first, note_point_id("/notes/a.md", 1));
+        assert_ne!(first, note_point_id("/notes/b.md", 0));
     }
 }
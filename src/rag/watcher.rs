@@ -0,0 +1,298 @@
+// src/rag/watcher.rs
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use log::{error, info, warn};
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+use crate::config::app_config::AppConfiguration;
+use crate::llama::LlamaService;
+use crate::prelude::*;
+use crate::rag::files::{
+    get_markdown_files, index_markdown_file, is_markdown_file, process_markdown_files,
+    prune_deleted_notes, IndexOutcome,
+};
+use crate::rag::vectors::VectorDB;
+
+/// How long the file system has to be quiet before a burst of events is processed
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(750);
+
+/// Changes made to the index by the watcher, reported to the TUI status bar
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    /// A full pass over the notes directory finished
+    Synced {
+        updated: usize,
+        removed: usize,
+    },
+    /// A note was added or changed and has been re-embedded
+    Indexed(PathBuf),
+    /// A note was deleted or moved away and its vectors were removed
+    Removed(PathBuf),
+    Failed(String),
+}
+
+/// Watches `AppConfiguration::scan_path` recursively and keeps VectorDB in sync with the
+/// notes on disk. Runs until the watcher fails or the surrounding task is aborted.
+///
+/// Every change is reported through `events`, if given.
+pub async fn watch_notes(
+    config: &AppConfiguration,
+    llama_service: &LlamaService,
+    vector_db: &VectorDB,
+    events: Option<UnboundedSender<WatchEvent>>,
+) -> Result<()> {
+    let notes = NotesWatcher {
+        config,
+        root: fs::canonicalize(&config.scan_path)?,
+        llama_service,
+        vector_db,
+        events,
+    };
+
+    // Catch up with changes made while nothing was watching
+    notes.sync_all().await;
+
+    let (sender, mut receiver) = unbounded_channel();
+    let mut debouncer = new_debouncer(WATCH_DEBOUNCE, move |result: DebounceEventResult| {
+        let _ = sender.send(result);
+    })
+    .map_err(|e| AppError::UnexpectedError(format!("Failed to start file watcher: {}", e)))?;
+
+    debouncer
+        .watcher()
+        .watch(Path::new(&config.scan_path), RecursiveMode::Recursive)
+        .map_err(|e| AppError::UnexpectedError(format!("Failed to watch notes: {}", e)))?;
+
+    info!("Watching {} for changes", config.scan_path);
+
+    while let Some(result) = receiver.recv().await {
+        match result {
+            Ok(debounced) => {
+                let paths: HashSet<PathBuf> = debounced
+                    .into_iter()
+                    .map(|event| notes.note_path(&event.path))
+                    .collect();
+                notes.handle_changes(paths).await;
+            }
+            Err(e) => {
+                error!("File watcher error: {}", e);
+                notes.report(WatchEvent::Failed(e.to_string()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+struct NotesWatcher<'a> {
+    config: &'a AppConfiguration,
+    /// Canonical form of the scan path, used to map absolute event paths back
+    root: PathBuf,
+    llama_service: &'a LlamaService,
+    vector_db: &'a VectorDB,
+    events: Option<UnboundedSender<WatchEvent>>,
+}
+
+impl NotesWatcher<'_> {
+    /// Maps a path reported by the watcher to the form produced by `get_markdown_files`,
+    /// which is the one stored in the `file_path` payload.
+    fn note_path(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.root) {
+            Ok(relative) => Path::new(&self.config.scan_path).join(relative),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+
+    async fn handle_changes(&self, paths: HashSet<PathBuf>) {
+        let mut needs_full_sync = false;
+        let mut removed = Vec::new();
+
+        for path in paths {
+            if is_markdown_file(&path) {
+                if path.is_file() {
+                    self.reindex_file(&path).await;
+                } else {
+                    self.remove_file(&path).await;
+                }
+            } else if path.is_dir() {
+                // A directory was created or moved in; the notes inside it are not
+                // reported one by one, so compare the whole tree with the index instead
+                needs_full_sync = true;
+            } else if !path.exists() {
+                // Either a deleted directory or a temporary file of an editor
+                removed.push(path);
+            }
+        }
+
+        if !needs_full_sync && !removed.is_empty() {
+            needs_full_sync = self.removes_indexed_notes(&removed).await;
+        }
+
+        if needs_full_sync {
+            self.sync_all().await;
+        }
+
+        if let Err(e) = self.vector_db.flush().await {
+            warn!("Error saving the index: {}", e);
+            self.report(WatchEvent::Failed(format!(
+                "Failed to save the index: {}",
+                e
+            )));
+        }
+    }
+
+    /// Whether any of the removed paths held indexed notes, so that it was a notes folder.
+    async fn removes_indexed_notes(&self, removed: &[PathBuf]) -> bool {
+        match self.vector_db.indexed_note_hashes().await {
+            Ok(indexed) => removed.iter().any(|path| {
+                contains_indexed_note(&self.note_path(path), indexed.keys().map(String::as_str))
+            }),
+            Err(e) => {
+                // Rescan rather than leave deleted notes in the index
+                warn!("Error reading the indexed notes: {}", e);
+                true
+            }
+        }
+    }
+
+    async fn reindex_file(&self, path: &Path) {
+        let result = async {
+            let indexed_hash = self
+                .vector_db
+                .indexed_note_hash(&path.to_string_lossy())
+                .await?;
+            index_markdown_file(
+                path,
+                indexed_hash.as_deref(),
+                false,
+                self.config,
+                self.llama_service,
+                self.vector_db,
+            )
+            .await
+        }
+        .await;
+
+        match result {
+            Ok(IndexOutcome::Unchanged) => {}
+            Ok(_) => self.report(WatchEvent::Indexed(path.to_path_buf())),
+            Err(e) => {
+                warn!("Error re-indexing {:?}: {}", path, e);
+                self.report(WatchEvent::Failed(format!(
+                    "Failed to re-index {}: {}",
+                    path.display(),
+                    e
+                )));
+            }
+        }
+    }
+
+    async fn remove_file(&self, path: &Path) {
+        let result = async {
+            let file_path = path.to_string_lossy();
+            // Notes that were never indexed, such as ones removed right after being
+            // created, have nothing to remove
+            if self
+                .vector_db
+                .indexed_note_hash(&file_path)
+                .await?
+                .is_none()
+            {
+                return Ok(false);
+            }
+            self.vector_db.delete_note_vectors(&file_path).await?;
+            Ok::<_, AppError>(true)
+        }
+        .await;
+
+        match result {
+            Ok(false) => {}
+            Ok(true) => {
+                info!("Removed vectors of deleted file: {:?}", path);
+                self.report(WatchEvent::Removed(path.to_path_buf()));
+            }
+            Err(e) => {
+                warn!("Error removing vectors of {:?}: {}", path, e);
+                self.report(WatchEvent::Failed(format!(
+                    "Failed to remove {}: {}",
+                    path.display(),
+                    e
+                )));
+            }
+        }
+    }
+
+    async fn sync_all(&self) {
+        let result = async {
+            let files = get_markdown_files(self.config)?;
+            let summary = process_markdown_files(
+                &files,
+                self.config,
+                self.llama_service,
+                self.vector_db,
+                false,
+            )
+            .await?;
+            let pruned = prune_deleted_notes(&files, self.vector_db, false).await?;
+            Ok::<_, AppError>((summary, pruned))
+        }
+        .await;
+
+        match result {
+            Ok((summary, pruned)) => self.report(WatchEvent::Synced {
+                updated: summary.added + summary.updated,
+                removed: pruned.len(),
+            }),
+            Err(e) => {
+                warn!("Error synchronising notes: {}", e);
+                self.report(WatchEvent::Failed(format!(
+                    "Failed to synchronise notes: {}",
+                    e
+                )));
+            }
+        }
+    }
+
+    fn report(&self, event: WatchEvent) {
+        if let Some(events) = &self.events {
+            let _ = events.send(event);
+        }
+    }
+}
+
+fn contains_indexed_note<'a>(dir: &Path, mut indexed: impl Iterator<Item = &'a str>) -> bool {
+    indexed.any(|note| Path::new(note).starts_with(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_indexed_note() {
+        let indexed = ["/notes/work/vpn.md", "/notes/todo.md"];
+
+        assert!(contains_indexed_note(
+            Path::new("/notes/work"),
+            indexed.into_iter()
+        ));
+        // Temporary files of editors and folders without notes are ignored
+        assert!(!contains_indexed_note(
+            Path::new("/notes/4913"),
+            indexed.into_iter()
+        ));
+        assert!(!contains_indexed_note(
+            Path::new("/notes/todo.md~"),
+            indexed.into_iter()
+        ));
+        assert!(!contains_indexed_note(
+            Path::new("/notes/wor"),
+            indexed.into_iter()
+        ));
+    }
+}
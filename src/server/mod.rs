@@ -0,0 +1,407 @@
+// src/server/mod.rs
+use std::convert::Infallible;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc::unbounded_channel;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::cli::FilterArgs;
+use crate::config::app_config::{AppConfiguration, VectorStoreKind};
+use crate::llama::answer::NoteSource;
+use crate::llama::LlamaService;
+use crate::prelude::*;
+use crate::rag::files::{index_markdown_file, is_markdown_file, IndexOutcome};
+use crate::rag::vectors::{IndexStats, VectorDB};
+
+pub const DEFAULT_PORT: u16 = 8737;
+
+/// Default number of hits returned by `/api/search`
+const DEFAULT_SEARCH_LIMIT: u64 = 10;
+
+struct ServerState {
+    config: AppConfiguration,
+    llama_service: Arc<LlamaService>,
+    vector_db: Arc<VectorDB>,
+}
+
+type SharedState = State<Arc<ServerState>>;
+
+#[derive(Debug, Deserialize)]
+struct SearchRequest {
+    query: String,
+    limit: Option<u64>,
+    /// Minimum embedding similarity of the hits, as with `search --min-score`
+    min_score: Option<f32>,
+    #[serde(flatten)]
+    filters: FilterArgs,
+}
+
+#[derive(Debug, Serialize)]
+struct SearchResponse {
+    hits: Vec<NoteSource>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AskRequest {
+    question: String,
+    top_k: Option<u64>,
+    #[serde(default = "default_stream")]
+    stream: bool,
+    #[serde(flatten)]
+    filters: FilterArgs,
+}
+
+fn default_stream() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+struct FileRequest {
+    path: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IndexFileResponse {
+    path: String,
+    outcome: IndexOutcome,
+}
+
+#[derive(Debug, Serialize)]
+struct DeleteFileResponse {
+    path: String,
+}
+
+#[derive(Debug, Serialize)]
+struct StatsResponse {
+    #[serde(flatten)]
+    index: IndexStats,
+    completion_model: String,
+    embedding_model: String,
+    vector_store: VectorStoreKind,
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            AppError::InputError(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = serde_json::json!({ "error": self.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+/// Serves the HTTP API on localhost until the process is interrupted.
+pub async fn serve(
+    config: AppConfiguration,
+    llama_service: Arc<LlamaService>,
+    vector_db: Arc<VectorDB>,
+    port: u16,
+) -> Result<()> {
+    let state = Arc::new(ServerState {
+        config,
+        llama_service,
+        vector_db,
+    });
+    let app = Router::new()
+        .route("/api/stats", get(stats))
+        .route("/api/search", post(search))
+        .route("/api/ask", post(ask))
+        .route("/api/index-file", post(index_file))
+        .route("/api/delete-file", post(delete_file))
+        .with_state(state);
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    let app = app.layer(middleware::from_fn_with_state(
+        listener.local_addr()?.port(),
+        check_host,
+    ));
+    info!("Serving the HTTP API on http://{}", listener.local_addr()?);
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+/// Rejects requests addressed to another host name, so that a web page can't read the
+/// notes by pointing its own domain at 127.0.0.1 (DNS rebinding).
+async fn check_host(State(port): State<u16>, request: Request, next: Next) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok());
+    if host.is_some_and(|host| is_local_host(host, port)) {
+        return next.run(request).await;
+    }
+
+    let body = serde_json::json!({ "error": "Only requests to localhost are served" });
+    (StatusCode::FORBIDDEN, Json(body)).into_response()
+}
+
+fn is_local_host(host: &str, port: u16) -> bool {
+    [format!("127.0.0.1:{}", port), format!("localhost:{}", port)]
+        .iter()
+        .any(|local| host.eq_ignore_ascii_case(local))
+}
+
+async fn stats(State(state): SharedState) -> Result<Json<StatsResponse>> {
+    let index = state.vector_db.stats().await?;
+    Ok(Json(StatsResponse {
+        index,
+        completion_model: state.config.completion_model.clone(),
+        embedding_model: state.config.embedding_model.clone(),
+        vector_store: state.config.vector_store,
+    }))
+}
+
+async fn search(
+    State(state): SharedState,
+    Json(request): Json<SearchRequest>,
+) -> Result<Json<SearchResponse>> {
+    let (query, filter) = request.filters.parse_query(&request.query);
+    if query.is_empty() {
+        return Err(AppError::InputError("The query has no search terms".into()));
+    }
+
+    let embedding = state.llama_service.get_embedding(&query).await?;
+    let hits = state
+        .vector_db
+        .search_notes(
+            &query,
+            embedding,
+            request.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).max(1),
+            &filter,
+        )
+        .await?
+        .iter()
+        .filter(|note| {
+            request
+                .min_score
+                .is_none_or(|min_score| note.is_similar(min_score))
+        })
+        .map(NoteSource::from_scored_note)
+        .collect();
+
+    Ok(Json(SearchResponse { hits }))
+}
+
+/// Answers a question, streaming the answer as server-sent events unless `stream` is
+/// false: a `token` event per generated token, then an `answer` event with the complete
+/// answer and its sources, or an `error` event.
+async fn ask(State(state): SharedState, Json(request): Json<AskRequest>) -> Result<Response> {
+    let (question, filter) = request.filters.parse_query(&request.question);
+    if question.is_empty() {
+        return Err(AppError::InputError("The question is empty".into()));
+    }
+    let llama_service = match request.top_k {
+        Some(0) => return Err(AppError::InputError("top_k must be at least 1".into())),
+        Some(top_k) => state.llama_service.with_top_k(top_k),
+        None => state.llama_service.as_ref().clone(),
+    };
+
+    if !request.stream {
+        let (tokens, mut token_receiver) = unbounded_channel();
+        let generate = llama_service.generate_rag_completion_stream(
+            &[],
+            &question,
+            &state.vector_db,
+            &filter,
+            tokens,
+        );
+        // Keep receiving, as generation stops once the receiver is dropped
+        let drain = async { while token_receiver.recv().await.is_some() {} };
+        let (answer, _) = tokio::join!(generate, drain);
+        return Ok(Json(answer?).into_response());
+    }
+
+    let (events, event_receiver) = unbounded_channel();
+    tokio::spawn(async move {
+        let (tokens, mut token_receiver) = unbounded_channel();
+        let generate = llama_service.generate_rag_completion_stream(
+            &[],
+            &question,
+            &state.vector_db,
+            &filter,
+            tokens,
+        );
+        let token_events = events.clone();
+        let forward = async move {
+            while let Some(token) = token_receiver.recv().await {
+                if token_events.send(json_event("token", &token)).is_err() {
+                    // The client went away; dropping the receiver stops generation
+                    break;
+                }
+            }
+        };
+        let (answer, _) = tokio::join!(generate, forward);
+
+        let event = match answer {
+            Ok(answer) => json_event("answer", &answer),
+            Err(e) => {
+                error!("Error answering the question: {}", e);
+                json_event("error", &serde_json::json!({ "error": e.to_string() }))
+            }
+        };
+        let _ = events.send(event);
+    });
+
+    Ok(Sse::new(UnboundedReceiverStream::new(event_receiver)).into_response())
+}
+
+fn json_event(name: &str, data: &impl Serialize) -> std::result::Result<Event, Infallible> {
+    Ok(Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string())))
+}
+
+async fn index_file(
+    State(state): SharedState,
+    Json(request): Json<FileRequest>,
+) -> Result<Json<IndexFileResponse>> {
+    let file_path = note_path(Path::new(&state.config.scan_path), &request.path)?;
+    if !file_path.is_file() {
+        return Err(AppError::InputError(format!(
+            "Note '{}' does not exist",
+            file_path.display()
+        )));
+    }
+
+    let path = file_path.to_string_lossy().to_string();
+    let indexed_hash = state.vector_db.indexed_note_hash(&path).await?;
+    let outcome = index_markdown_file(
+        &file_path,
+        indexed_hash.as_deref(),
+        false,
+        &state.config,
+        &state.llama_service,
+        &state.vector_db,
+    )
+    .await?;
+    state.vector_db.flush().await?;
+
+    Ok(Json(IndexFileResponse { path, outcome }))
+}
+
+async fn delete_file(
+    State(state): SharedState,
+    Json(request): Json<FileRequest>,
+) -> Result<Json<DeleteFileResponse>> {
+    let file_path = note_path(Path::new(&state.config.scan_path), &request.path)?;
+
+    let path = file_path.to_string_lossy().to_string();
+    state.vector_db.delete_note_vectors(&path).await?;
+    state.vector_db.flush().await?;
+
+    Ok(Json(DeleteFileResponse { path }))
+}
+
+/// Resolves a note path from a request, relative to the notes directory unless absolute.
+/// Only markdown files inside the notes directory are accepted, also once symlinks are
+/// followed.
+fn note_path(scan_path: &Path, path: &str) -> Result<PathBuf> {
+    let path = Path::new(path);
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(AppError::InputError(format!(
+            "'{}' must not contain '..'",
+            path.display()
+        )));
+    }
+
+    let file_path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        scan_path.join(path)
+    };
+    let outside = match (real_path(scan_path), real_path(&file_path)) {
+        (Some(root), Some(real)) => !real.starts_with(root),
+        _ => !file_path.starts_with(scan_path),
+    };
+    if outside {
+        return Err(AppError::InputError(format!(
+            "'{}' is outside the notes directory",
+            path.display()
+        )));
+    }
+    if !is_markdown_file(&file_path) {
+        return Err(AppError::InputError(format!(
+            "'{}' is not a markdown note",
+            path.display()
+        )));
+    }
+
+    Ok(file_path)
+}
+
+/// Resolves symlinks in a path, or in its parent directory if it doesn't exist (as for a
+/// deleted note). Returns `None` if neither exists.
+fn real_path(path: &Path) -> Option<PathBuf> {
+    if let Ok(real) = path.canonicalize() {
+        return Some(real);
+    }
+    let parent = path.parent()?.canonicalize().ok()?;
+    Some(parent.join(path.file_name()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_is_local_host() {
+        assert!(is_local_host("127.0.0.1:8737", 8737));
+        assert!(is_local_host("LOCALHOST:8737", 8737));
+        assert!(!is_local_host("localhost:8080", 8737));
+        assert!(!is_local_host("evil.example:8737", 8737));
+        assert!(!is_local_host("localhost", 8737));
+    }
+
+    #[test]
+    fn test_note_path() {
+        let scan_path = Path::new("/notes");
+
+        assert_eq!(
+            note_path(scan_path, "work/vpn.md").unwrap(),
+            PathBuf::from("/notes/work/vpn.md")
+        );
+        assert_eq!(
+            note_path(scan_path, "/notes/vpn.md").unwrap(),
+            PathBuf::from("/notes/vpn.md")
+        );
+        assert!(note_path(scan_path, "../secrets.md").is_err());
+        assert!(note_path(scan_path, "/etc/notes.md").is_err());
+        assert!(note_path(scan_path, "work/vpn.txt").is_err());
+    }
+
+    #[test]
+    fn test_note_path_follows_symlinks() {
+        let notes = TempDir::new("notes").unwrap();
+        let outside = TempDir::new("outside").unwrap();
+        let secret = outside.path().join("secret.md");
+        std::fs::write(&secret, "# Secret").unwrap();
+        std::os::unix::fs::symlink(&secret, notes.path().join("link.md")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), notes.path().join("folder")).unwrap();
+        std::fs::write(notes.path().join("note.md"), "# Note").unwrap();
+
+        assert!(note_path(notes.path(), "note.md").is_ok());
+        assert!(note_path(notes.path(), "deleted.md").is_ok());
+        assert!(note_path(notes.path(), "link.md").is_err());
+        assert!(note_path(notes.path(), "folder/secret.md").is_err());
+        assert!(note_path(notes.path(), "folder/deleted.md").is_err());
+    }
+}
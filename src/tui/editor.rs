@@ -0,0 +1,110 @@
+// src/tui/editor.rs
+use std::path::{Path, PathBuf};
+
+use tokio::process::Command;
+
+use crate::prelude::*;
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` is set
+const FALLBACK_EDITOR: &str = "vi";
+
+/// A note to open in the user's editor once the TUI is suspended
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditRequest {
+    pub path: PathBuf,
+    /// 1-based line the cursor is placed on
+    pub line: usize,
+}
+
+/// Returns the 1-based line containing the byte offset, clamped to the text.
+pub fn line_at(content: &str, byte_offset: usize) -> usize {
+    let end = byte_offset.min(content.len());
+    content.as_bytes()[..end]
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count()
+        + 1
+}
+
+/// The editor command line from `$VISUAL` or `$EDITOR`, e.g. `code --wait`.
+fn configured_editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|variable| std::env::var(variable).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| FALLBACK_EDITOR.to_string())
+}
+
+/// Builds the command opening `path` at `line` in `editor`.
+///
+/// VS Code style editors take `--goto file:line`; vi, Emacs, nano, Helix and most others
+/// understand `+line file`.
+fn editor_command(editor: &str, path: &Path, line: usize) -> Result<Command> {
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| AppError::ConfigError("The editor command is empty".into()))?;
+
+    let mut command = Command::new(program);
+    command.args(words);
+
+    let name = Path::new(program)
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if matches!(
+        name.as_str(),
+        "code" | "codium" | "code-insiders" | "cursor"
+    ) {
+        command
+            .arg("--goto")
+            .arg(format!("{}:{}", path.display(), line));
+    } else {
+        command.arg(format!("+{}", line)).arg(path);
+    }
+
+    Ok(command)
+}
+
+/// Runs the user's editor on the note and waits for it to exit.
+pub async fn open_in_editor(request: &EditRequest) -> Result<()> {
+    let editor = configured_editor();
+    let status = editor_command(&editor, &request.path, request.line)?
+        .status()
+        .await
+        .map_err(|e| AppError::TUIError(format!("Failed to start editor '{}': {}", editor, e)))?;
+
+    if !status.success() {
+        return Err(AppError::TUIError(format!(
+            "Editor '{}' exited with {}",
+            editor, status
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_at() {
+        let content = "# Title\n\n## Setup\nżółw\n";
+        assert_eq!(line_at(content, 0), 1);
+        assert_eq!(line_at(content, content.find("## Setup").unwrap()), 3);
+        assert_eq!(line_at(content, 1000), 5);
+    }
+
+    #[test]
+    fn test_editor_command_line_argument() {
+        let path = Path::new("/notes/todo.md");
+
+        let vim = editor_command("nvim", path, 12).unwrap();
+        let args: Vec<_> = vim.as_std().get_args().collect();
+        assert_eq!(args, ["+12", "/notes/todo.md"]);
+
+        let code = editor_command("/usr/bin/code --wait", path, 3).unwrap();
+        let args: Vec<_> = code.as_std().get_args().collect();
+        assert_eq!(args, ["--wait", "--goto", "/notes/todo.md:3"]);
+    }
+}
@@ -0,0 +1,380 @@
+// src/tui/markdown.rs
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+
+/// Words highlighted in code blocks, shared by the languages common in notes
+const CODE_KEYWORDS: &str = "as async await break case class const continue def do elif else \
+    enum export false fn for from func function if impl import in interface let loop match mod \
+    mut None null pub return self static struct switch then trait true type use var where while \
+    with yield";
+
+const QUOTE_STYLE: Style = Style::new().fg(Color::DarkGray);
+const CODE_STYLE: Style = Style::new().fg(Color::Yellow);
+const KEYWORD_STYLE: Style = Style::new().fg(Color::Magenta);
+const STRING_STYLE: Style = Style::new().fg(Color::Green);
+const COMMENT_STYLE: Style = Style::new().fg(Color::DarkGray);
+const NUMBER_STYLE: Style = Style::new().fg(Color::Cyan);
+const LINK_STYLE: Style = Style::new()
+    .fg(Color::Blue)
+    .add_modifier(Modifier::UNDERLINED);
+
+/// Renders markdown as styled terminal text.
+///
+/// Line breaks within paragraphs are kept, as notes are often wrapped by hand.
+pub fn render_markdown(markdown: &str) -> Text<'static> {
+    let mut renderer = Renderer::default();
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+
+    for event in Parser::new_ext(markdown, options) {
+        renderer.handle(event);
+    }
+
+    renderer.flush_line();
+    while renderer
+        .lines
+        .last()
+        .is_some_and(|line| line.spans.is_empty())
+    {
+        renderer.lines.pop();
+    }
+    Text::from(renderer.lines)
+}
+
+#[derive(Default)]
+struct Renderer {
+    lines: Vec<Line<'static>>,
+    /// Spans of the line being built
+    spans: Vec<Span<'static>>,
+    /// Inline styles of the enclosing tags, innermost last
+    styles: Vec<Style>,
+    /// Enclosing lists with the next number of ordered ones
+    lists: Vec<Option<u64>>,
+    /// Bullet of a list item whose first line hasn't started yet
+    pending_bullet: Option<String>,
+    quote_depth: usize,
+    /// Language of the fenced code block being rendered
+    code_block: Option<String>,
+    /// Targets of the enclosing links
+    links: Vec<String>,
+}
+
+impl Renderer {
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match self.code_block.clone() {
+                Some(language) => {
+                    for line in text.lines() {
+                        self.start_line();
+                        self.spans.extend(highlight_code_line(line, &language));
+                        self.flush_line();
+                    }
+                }
+                None => self.push_text(&text),
+            },
+            Event::Code(code) => self.push_span(Span::styled(code.to_string(), CODE_STYLE)),
+            Event::Html(html) | Event::InlineHtml(html) => {
+                for line in html.lines() {
+                    self.push_span(Span::styled(line.to_string(), QUOTE_STYLE));
+                    self.flush_line();
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => self.flush_line(),
+            Event::Rule => {
+                self.flush_line();
+                self.push_span(Span::styled("─".repeat(20), QUOTE_STYLE));
+                self.flush_line();
+                self.blank_line();
+            }
+            Event::TaskListMarker(checked) => {
+                self.push_span(Span::raw(if checked { "[x] " } else { "[ ] " }));
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush_line();
+                let style = match level {
+                    HeadingLevel::H1 => Style::new()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    HeadingLevel::H2 => Style::new().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                    _ => Style::new().add_modifier(Modifier::BOLD),
+                };
+                self.styles.push(style);
+            }
+            Tag::Emphasis => self.push_style(Style::new().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::new().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self.push_style(Style::new().add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link { dest_url, .. } => {
+                self.links.push(dest_url.to_string());
+                self.push_style(LINK_STYLE);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush_line();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush_line();
+                let language = match kind {
+                    CodeBlockKind::Fenced(language) => language.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code_block = Some(language);
+            }
+            Tag::List(start) => {
+                self.flush_line();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush_line();
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.pending_bullet = Some(format!("{}{}", "  ".repeat(depth), marker));
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                self.styles.pop();
+                self.flush_line();
+                self.blank_line();
+            }
+            TagEnd::Paragraph => {
+                self.flush_line();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link => {
+                self.styles.pop();
+                if let Some(url) = self.links.pop() {
+                    let text: String = self
+                        .spans
+                        .iter()
+                        .map(|span| span.content.as_ref())
+                        .collect();
+                    if !url.is_empty() && !text.ends_with(&url) {
+                        self.push_span(Span::styled(format!(" ({})", url), QUOTE_STYLE));
+                    }
+                }
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_line();
+                self.quote_depth -= 1;
+                if self.quote_depth == 0 {
+                    self.blank_line();
+                }
+            }
+            TagEnd::CodeBlock => {
+                self.code_block = None;
+                self.blank_line();
+            }
+            TagEnd::List(_) => {
+                self.flush_line();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            }
+            TagEnd::Item => self.flush_line(),
+            _ => {}
+        }
+    }
+
+    fn push_style(&mut self, style: Style) {
+        let current = self.styles.last().copied().unwrap_or_default();
+        self.styles.push(current.patch(style));
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let style = self.styles.last().copied().unwrap_or_default();
+        let style = if self.quote_depth > 0 {
+            style.add_modifier(Modifier::ITALIC)
+        } else {
+            style
+        };
+        self.push_span(Span::styled(text.to_string(), style));
+    }
+
+    fn push_span(&mut self, span: Span<'static>) {
+        if self.spans.is_empty() {
+            self.start_line();
+        }
+        self.spans.push(span);
+    }
+
+    /// Adds the quote markers and list indentation that start every line.
+    fn start_line(&mut self) {
+        if self.quote_depth > 0 {
+            self.spans
+                .push(Span::styled("│ ".repeat(self.quote_depth), QUOTE_STYLE));
+        }
+        if let Some(bullet) = self.pending_bullet.take() {
+            self.spans.push(Span::raw(bullet));
+        } else if !self.lists.is_empty() {
+            self.spans.push(Span::raw("  ".repeat(self.lists.len())));
+        }
+    }
+
+    fn flush_line(&mut self) {
+        if !self.spans.is_empty() {
+            self.lines.push(Line::from(std::mem::take(&mut self.spans)));
+        }
+    }
+
+    /// Separates blocks by an empty line, without stacking several.
+    fn blank_line(&mut self) {
+        if self.lines.last().is_some_and(|line| !line.spans.is_empty()) {
+            self.lines.push(Line::default());
+        }
+    }
+}
+
+/// Colours keywords, strings, numbers and comments of a line of code.
+fn highlight_code_line(line: &str, language: &str) -> Vec<Span<'static>> {
+    let comment_marker = match language {
+        "python" | "py" | "sh" | "bash" | "zsh" | "shell" | "yaml" | "yml" | "toml" | "ruby"
+        | "rb" | "conf" => "#",
+        "sql" | "lua" | "haskell" => "--",
+        _ => "//",
+    };
+
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        let token_len = if rest.starts_with(comment_marker) {
+            push_plain(&mut spans, &mut plain);
+            spans.push(Span::styled(rest.to_string(), COMMENT_STYLE));
+            break;
+        } else if c == '"' || c == '\'' || c == '`' {
+            // Strings run to the closing quote or the end of the line
+            let end = rest[1..]
+                .find(c)
+                .map_or(rest.len(), |end| end + 1 + c.len_utf8());
+            push_plain(&mut spans, &mut plain);
+            spans.push(Span::styled(rest[..end].to_string(), STRING_STYLE));
+            end
+        } else if c.is_alphanumeric() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            if CODE_KEYWORDS
+                .split_whitespace()
+                .any(|keyword| keyword == word)
+            {
+                push_plain(&mut spans, &mut plain);
+                spans.push(Span::styled(word.to_string(), KEYWORD_STYLE));
+            } else if c.is_ascii_digit() {
+                push_plain(&mut spans, &mut plain);
+                spans.push(Span::styled(word.to_string(), NUMBER_STYLE));
+            } else {
+                plain.push_str(word);
+            }
+            end
+        } else {
+            plain.push(c);
+            c.len_utf8()
+        };
+        rest = &rest[token_len..];
+    }
+
+    push_plain(&mut spans, &mut plain);
+    spans
+}
+
+/// Moves the collected plain code into a span.
+fn push_plain(spans: &mut Vec<Span<'static>>, plain: &mut String) {
+    if !plain.is_empty() {
+        spans.push(Span::styled(std::mem::take(plain), CODE_STYLE));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let text = render_markdown(
+            "# Setup\n\nInstall **ripgrep** first.\n\n- one\n- two\n  1. nested\n\n> quoted\n",
+        );
+        let lines: Vec<String> = text.lines.iter().map(line_text).collect();
+
+        assert_eq!(
+            lines,
+            [
+                "Setup",
+                "",
+                "Install ripgrep first.",
+                "",
+                "• one",
+                "• two",
+                "  1. nested",
+                "",
+                "│ quoted",
+            ]
+        );
+        assert!(text.lines[0].spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+        assert!(text.lines[2].spans[1]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_render_code_and_links() {
+        let text = render_markdown(
+            "See [the docs](https://example.com).\n\n```rust\nlet x = \"hi\"; // note\n```\n",
+        );
+        let lines: Vec<String> = text.lines.iter().map(line_text).collect();
+
+        assert_eq!(
+            lines,
+            [
+                "See the docs (https://example.com).",
+                "",
+                "let x = \"hi\"; // note"
+            ]
+        );
+
+        let code = &text.lines[2].spans;
+        assert_eq!(code[0].content, "let");
+        assert_eq!(code[0].style, KEYWORD_STYLE);
+        assert_eq!(code[2].content, "\"hi\"");
+        assert_eq!(code[2].style, STRING_STYLE);
+        assert_eq!(code.last().unwrap().style, COMMENT_STYLE);
+    }
+}
@@ -1,41 +1,226 @@
 // src/tui/mod.rs
+use std::fmt;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    cursor::Show,
+    event::{
+        DisableMouseCapture, EnableMouseCapture, Event, EventStream, KeyCode, KeyEvent,
+        KeyEventKind, KeyModifiers, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use qdrant_client::prelude::point_id::PointIdOptions;
+use log::{error, warn};
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Frame, Terminal,
 };
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+
+use crate::config::app_config::AppConfiguration;
+use crate::error::AppError;
+use crate::llama::answer::{cited_sources, NoteSource, RagAnswer};
+use crate::llama::context::truncate_chars;
+use crate::llama::conversation::{ChatMessage, Role};
+use crate::llama::LlamaService;
+use crate::tui::editor::{line_at, open_in_editor, EditRequest};
+use crate::tui::markdown::render_markdown;
+use crate::tui::scroll::{ScrollView, WHEEL_SCROLL_LINES};
+use crate::tui::settings::{config_to_save, SettingsField, SettingsForm, SETTINGS_FIELDS};
+
+pub mod editor;
+pub mod markdown;
+pub mod scroll;
+pub mod settings;
+use crate::{
+    prelude::Result,
+    rag::{
+        files::{index_markdown_file, IndexOutcome},
+        filters::{parse_query, SearchFilter},
+        frontmatter::MarkdownNote,
+        vectors::{NotePayload, ScoredNote, VectorDB},
+        watcher::{watch_notes, WatchEvent},
+    },
+};
+
+/// How long background indexing updates stay in the status bar
+const WATCH_STATUS_DURATION: Duration = Duration::from_secs(3);
+
+/// How long the connection status is shown after start-up
+const STARTUP_STATUS_DURATION: Duration = Duration::from_secs(3);
+
+/// How long the outcome of a search stays in the status bar
+const RESULT_STATUS_DURATION: Duration = Duration::from_secs(5);
+
+/// Interval of the UI timer that expires status messages and animates the spinner
+const TICK_RATE: Duration = Duration::from_millis(100);
+
+/// Frames of the spinner shown while a background task is running
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
-use crate::{error::AppError, llama::LlamaService};
-use crate::{prelude::Result, rag::vectors::VectorDB};
+/// Number of notes shown in the Search tab
+const SEARCH_RESULT_LIMIT: u64 = 10;
 
 pub struct App {
     pub input: String,
     pub cursor_position: usize,
-    pub messages: Vec<String>,
+    pub messages: Vec<ChatMessage>,
     pub selected_tab: Tab,
     pub search_results: Vec<SearchResult>,
     pub selected_result: Option<usize>,
+    /// Index of the selected source among the sources of all chat messages
+    pub selected_source: Option<usize>,
+    pub chat_scroll: ScrollView,
+    pub preview_scroll: ScrollView,
     pub status_message: Option<String>,
+    pub status_expires_at: Option<Instant>,
+    /// The chat reply that is currently being streamed into the last message
+    pub generation: Option<BackgroundTask>,
+    /// The search that is currently running
+    pub search: Option<BackgroundTask>,
+    /// Advanced on every tick while a background task is running
+    pub spinner_frame: usize,
+    next_task_id: u64,
+    /// Note to open in the editor, handled by the UI loop as it owns the terminal
+    pub pending_edit: Option<EditRequest>,
+    pub settings: SettingsForm,
+    /// Whether settings are being checked and saved
+    pub applying_settings: bool,
+    /// Services connected with new settings, swapped in by the UI loop
+    pending_services: Option<ConnectedServices>,
+    /// Why chat and semantic search are unavailable, if the LLM provider can't be used
+    pub llm_problem: Option<ServiceProblem>,
+    /// Why the notes can't be searched, if the vector store can't be used
+    pub store_problem: Option<ServiceProblem>,
+    pub should_quit: bool,
+}
+
+/// A service the TUI could not connect to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceProblem {
+    /// Shown in the tab bar, e.g. "Ollama unreachable"
+    pub summary: String,
+    pub detail: String,
+}
+
+impl ServiceProblem {
+    fn new(summary: impl Into<String>, detail: impl fmt::Display) -> Self {
+        Self {
+            summary: summary.into(),
+            detail: detail.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ServiceProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.summary, self.detail)
+    }
+}
+
+/// Outcome of connecting to the services in the background at start-up
+pub struct ConnectionCheck {
+    /// The models offered by the LLM provider
+    models: std::result::Result<Vec<String>, ServiceProblem>,
+    store: std::result::Result<(), ServiceProblem>,
+}
+
+/// A spawned LLM or search request. Its messages carry the task ID, so that messages of
+/// a cancelled task can be told apart from those of its replacement.
+pub struct BackgroundTask {
+    id: u64,
+    handle: JoinHandle<()>,
+}
+
+/// Messages sent to the UI loop by background tasks
+pub enum AppMessage {
+    /// A piece of the chat reply of task `id`
+    Token { id: u64, token: String },
+    /// The chat reply of task `id` is complete
+    ReplyFinished { id: u64, result: Result<RagAnswer> },
+    /// The search of task `id` is complete
+    SearchFinished {
+        id: u64,
+        result: Result<Vec<ScoredNote>>,
+    },
+    /// The notes watcher changed the index
+    Watch(WatchEvent),
+    /// A note edited from the TUI was re-indexed
+    Reindexed {
+        path: PathBuf,
+        result: Result<IndexOutcome>,
+    },
+    /// The services were checked at start-up
+    Connected(ConnectionCheck),
+    /// The settings were saved and services connected with them
+    SettingsApplied(Result<ConnectedServices>),
+}
+
+/// Services shared with the tasks spawned by the UI
+struct Services {
+    config: Arc<AppConfiguration>,
+    llama_service: Arc<LlamaService>,
+    vector_db: Arc<VectorDB>,
+    messages: UnboundedSender<AppMessage>,
+    /// Whether changed notes are re-indexed in the background
+    watch: bool,
+    /// Task connecting at start-up, then watching the notes with the current services
+    background: JoinHandle<()>,
+}
+
+/// Services connected with settings edited in the Settings tab
+pub struct ConnectedServices {
+    config: AppConfiguration,
+    llama_service: Arc<LlamaService>,
+    /// Only reconnected when the vector store settings changed
+    vector_db: Option<Arc<VectorDB>>,
+    models: Vec<String>,
+}
+
+impl Services {
+    fn apply(&mut self, connected: ConnectedServices) {
+        self.config = Arc::new(connected.config);
+        self.llama_service = connected.llama_service;
+        if let Some(vector_db) = connected.vector_db {
+            self.vector_db = vector_db;
+        }
+
+        // The start-up check is outdated, and the watcher must follow the new notes
+        // directory and services
+        self.background.abort();
+        if self.watch {
+            let config = self.config.clone();
+            let llama_service = self.llama_service.clone();
+            let vector_db = self.vector_db.clone();
+            let messages = self.messages.clone();
+            self.background = tokio::spawn(async move {
+                watch_in_background(&config, &llama_service, &vector_db, messages).await;
+            });
+        }
+    }
 }
 
 pub struct SearchResult {
     pub id: String,
     pub title: String,
+    pub section: String,
     pub content: String,
     pub content_preview: String,
     pub score: f32,
     pub file_path: String,
+    pub tags: Vec<String>,
+    /// Offset of the chunk within the note file
+    pub byte_start: usize,
 }
 
 pub enum Tab {
@@ -53,16 +238,225 @@ impl App {
             selected_tab: Tab::Chat,
             search_results: Vec::new(),
             selected_result: None,
+            selected_source: None,
+            chat_scroll: ScrollView::following(),
+            preview_scroll: ScrollView::default(),
             status_message: None,
+            status_expires_at: None,
+            generation: None,
+            search: None,
+            spinner_frame: 0,
+            next_task_id: 0,
+            pending_edit: None,
+            settings: SettingsForm::default(),
+            applying_settings: false,
+            pending_services: None,
+            llm_problem: None,
+            store_problem: None,
+            should_quit: false,
         }
     }
 
     pub fn set_status(&mut self, message: impl Into<String>) {
         self.status_message = Some(message.into());
+        self.status_expires_at = None;
+    }
+
+    /// Shows a status message that is cleared automatically after `duration`.
+    pub fn set_timed_status(&mut self, message: impl Into<String>, duration: Duration) {
+        self.status_message = Some(message.into());
+        self.status_expires_at = Some(Instant::now() + duration);
     }
 
     pub fn clear_status(&mut self) {
         self.status_message = None;
+        self.status_expires_at = None;
+    }
+
+    pub fn expire_status(&mut self) {
+        if self
+            .status_expires_at
+            .is_some_and(|expires_at| Instant::now() >= expires_at)
+        {
+            self.clear_status();
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.generation.is_some() || self.search.is_some() || self.applying_settings
+    }
+
+    /// Expires status messages and advances the spinner.
+    pub fn on_tick(&mut self) {
+        self.expire_status();
+        if self.is_busy() {
+            self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
+        }
+    }
+
+    fn next_task_id(&mut self) -> u64 {
+        self.next_task_id += 1;
+        self.next_task_id
+    }
+
+    pub fn on_message(&mut self, message: AppMessage) {
+        match message {
+            AppMessage::Token { id, token } => {
+                if is_current(&self.generation, id) {
+                    if let Some(reply) = self.messages.last_mut() {
+                        reply.content.push_str(&token);
+                    }
+                }
+            }
+            AppMessage::ReplyFinished { id, result } => {
+                if !is_current(&self.generation, id) {
+                    return;
+                }
+                self.generation = None;
+                self.clear_status();
+
+                match result {
+                    Ok(answer) => {
+                        if let Some(reply) = self.messages.last_mut() {
+                            reply.content = answer.answer;
+                            reply.sources = answer.sources;
+                        }
+                        if !answer.dropped.is_empty() {
+                            self.set_timed_status(
+                                format!(
+                                    "{} retrieved passages were left out of the prompt",
+                                    answer.dropped.len()
+                                ),
+                                RESULT_STATUS_DURATION,
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        // Remove the empty reply, or keep the part that was already streamed
+                        if self
+                            .messages
+                            .last()
+                            .is_some_and(|reply| reply.content.is_empty())
+                        {
+                            self.messages.pop();
+                        }
+                        self.set_status(format!("Error generating response: {}", e));
+                    }
+                }
+            }
+            AppMessage::SearchFinished { id, result } => {
+                if !is_current(&self.search, id) {
+                    return;
+                }
+                self.search = None;
+                self.clear_status();
+                self.show_search_results(result);
+            }
+            AppMessage::Watch(event) => self.on_watch_event(event),
+            AppMessage::Reindexed { path, result } => {
+                let message = match result {
+                    Ok(IndexOutcome::Unchanged) => format!("{} is unchanged", path.display()),
+                    Ok(_) => format!("Re-indexed {}", path.display()),
+                    Err(e) => format!("Error re-indexing {}: {}", path.display(), e),
+                };
+                self.set_timed_status(message, RESULT_STATUS_DURATION);
+            }
+            AppMessage::Connected(check) => self.on_connected(check),
+            AppMessage::SettingsApplied(result) => {
+                self.applying_settings = false;
+                match result {
+                    Ok(connected) => {
+                        self.llm_problem = None;
+                        self.store_problem = None;
+                        self.settings.models = connected.models.clone();
+                        self.pending_services = Some(connected);
+                        self.set_timed_status("Settings saved and applied", RESULT_STATUS_DURATION);
+                    }
+                    Err(e) => self.set_status(format!("Settings not applied: {}", e)),
+                }
+            }
+        }
+    }
+
+    fn on_connected(&mut self, check: ConnectionCheck) {
+        match check.models {
+            Ok(models) => self.settings.models = models,
+            Err(problem) => {
+                warn!("{}", problem);
+                self.llm_problem = Some(problem);
+            }
+        }
+        if let Err(problem) = check.store {
+            warn!("{}", problem);
+            self.store_problem = Some(problem);
+        }
+
+        match self.store_problem.as_ref().or(self.llm_problem.as_ref()) {
+            Some(problem) => self.set_status(format!(
+                "{}. Fix the settings and press Enter in the Settings tab to reconnect.",
+                problem
+            )),
+            None => self.set_timed_status(
+                "Connected to the LLM provider and the vector store",
+                STARTUP_STATUS_DURATION,
+            ),
+        }
+    }
+
+    /// Why a feature needing the LLM provider and the vector store is unavailable
+    fn chat_problem(&self) -> Option<&ServiceProblem> {
+        self.store_problem.as_ref().or(self.llm_problem.as_ref())
+    }
+
+    fn show_search_results(&mut self, result: Result<Vec<ScoredNote>>) {
+        match result {
+            Ok(results) if results.is_empty() => {
+                self.set_timed_status(
+                    "No relevant notes found for your query.",
+                    RESULT_STATUS_DURATION,
+                );
+            }
+            Ok(results) => {
+                let mut found = format!("Found {} relevant notes.", results.len());
+                if let Some(problem) = &self.llm_problem {
+                    found.push_str(&format!(" Keyword matches only, {}.", problem.summary));
+                }
+                self.set_timed_status(found, RESULT_STATUS_DURATION);
+
+                for note in results {
+                    self.add_search_result(note.id, note.payload, note.score);
+                }
+
+                // Select first result by default
+                if !self.search_results.is_empty() {
+                    self.selected_result = Some(0);
+                }
+            }
+            Err(e) => {
+                self.set_status(format!("Error searching notes: {}", e));
+            }
+        }
+    }
+
+    pub fn on_watch_event(&mut self, event: WatchEvent) {
+        let message = match event {
+            WatchEvent::Synced {
+                updated: 0,
+                removed: 0,
+            } => return,
+            WatchEvent::Synced { updated, removed } => format!(
+                "Background re-index: {} notes updated, {} removed",
+                updated, removed
+            ),
+            WatchEvent::Indexed(path) => format!("Re-indexed {}", path.display()),
+            WatchEvent::Removed(path) => format!("Removed {} from index", path.display()),
+            WatchEvent::Failed(message) => message,
+        };
+
+        // Don't hide the status of a request that is still running
+        if self.status_message.is_none() || self.status_expires_at.is_some() {
+            self.set_timed_status(message, WATCH_STATUS_DURATION);
+        }
     }
 
     pub fn next_result(&mut self) {
@@ -76,6 +470,7 @@ impl App {
                 }
                 _ => {}
             }
+            self.preview_scroll.scroll_to_top();
         }
     }
 
@@ -90,48 +485,145 @@ impl App {
                 }
                 _ => {}
             }
+            self.preview_scroll.scroll_to_top();
         }
     }
 
-    pub fn view_full_content(&self) -> Option<String> {
-        match self.selected_result {
-            Some(idx) => self.search_results.get(idx).map(|result| {
-                format!(
-                    "# {}\n\nPath: {}\n\n{}",
-                    result.title, result.file_path, result.content_preview
-                )
-            }),
-            None => None,
+    /// Sources of all chat messages, in the order they are shown
+    fn sources(&self) -> impl Iterator<Item = &NoteSource> {
+        self.messages.iter().flat_map(|message| {
+            cited_sources(&message.content, &message.sources)
+                .into_iter()
+                .map(|(_, source)| source)
+        })
+    }
+
+    pub fn next_source(&mut self) {
+        let count = self.sources().count();
+        if count > 0 {
+            self.selected_source = Some(match self.selected_source {
+                Some(idx) if idx + 1 < count => idx + 1,
+                Some(idx) => idx,
+                None => 0,
+            });
         }
     }
-    pub fn add_search_result(
-        &mut self,
-        id: String,
-        title: String,
-        content: String,
-        score: f32,
-        file_path: String,
-    ) {
-        // Create a short preview of the content
-        let content_preview = if content.len() > 100 {
-            format!("{}...", &content[..100])
-        } else {
-            content.clone()
+
+    pub fn previous_source(&mut self) {
+        let count = self.sources().count();
+        if count > 0 {
+            self.selected_source = Some(match self.selected_source {
+                Some(idx) => idx.saturating_sub(1),
+                None => count - 1,
+            });
+        }
+    }
+
+    /// Shows the note of the selected chat source in the Search tab preview.
+    pub fn open_selected_source(&mut self) {
+        let Some(source) = self
+            .selected_source
+            .and_then(|idx| self.sources().nth(idx))
+            .cloned()
+        else {
+            return;
+        };
+
+        // Show the whole note without its frontmatter, falling back to the snippet if
+        // the file is gone
+        let (content, tags) = match std::fs::read_to_string(&source.file_path) {
+            Ok(text) => {
+                let note = MarkdownNote::parse(Path::new(&source.file_path), &text);
+                (note.body, note.frontmatter.tags)
+            }
+            Err(e) => {
+                self.set_timed_status(
+                    format!("Could not read {}: {}", source.file_path, e),
+                    RESULT_STATUS_DURATION,
+                );
+                (source.snippet.clone(), Vec::new())
+            }
         };
 
+        self.clear_search_results();
+        self.search_results.push(SearchResult {
+            id: source.id,
+            title: source.title,
+            section: source.section,
+            content,
+            content_preview: source.snippet,
+            score: source.score,
+            file_path: source.file_path,
+            tags,
+            byte_start: source.byte_start,
+        });
+        self.selected_result = Some(0);
+        self.selected_tab = Tab::Search;
+    }
+
+    /// Asks the UI loop to open the selected search result in the editor, at the line
+    /// where its chunk starts.
+    pub fn edit_selected_result(&mut self) {
+        let Some(result) = self
+            .selected_result
+            .and_then(|idx| self.search_results.get(idx))
+        else {
+            return;
+        };
+
+        let path = PathBuf::from(&result.file_path);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                let line = line_at(&content, result.byte_start);
+                self.pending_edit = Some(EditRequest { path, line });
+            }
+            Err(e) => self.set_timed_status(
+                format!("Could not open {}: {}", path.display(), e),
+                RESULT_STATUS_DURATION,
+            ),
+        }
+    }
+
+    pub fn add_search_result(&mut self, id: String, payload: NotePayload, score: f32) {
+        let section = payload.section_label();
+        let NotePayload {
+            title,
+            content,
+            file_path,
+            tags,
+            byte_start,
+            ..
+        } = payload;
+
+        // Create a short preview of the content
+        let content_preview = truncate_chars(&content, 100);
+
         self.search_results.push(SearchResult {
             id,
             title,
+            section,
             content,
             content_preview,
             score,
             file_path,
+            tags,
+            byte_start,
         });
     }
 
     pub fn clear_search_results(&mut self) {
         self.search_results.clear();
         self.selected_result = None;
+        self.preview_scroll.scroll_to_top();
+    }
+
+    /// The scrollable pane of the current tab
+    fn scroll_view(&mut self) -> Option<&mut ScrollView> {
+        match self.selected_tab {
+            Tab::Chat => Some(&mut self.chat_scroll),
+            Tab::Search => Some(&mut self.preview_scroll),
+            Tab::Settings => None,
+        }
     }
 
     pub fn move_cursor_left(&mut self) {
@@ -158,15 +650,31 @@ impl App {
         }
     }
 
-    pub fn submit_message(&mut self) -> String {
-        let message = std::mem::take(&mut self.input);
+    /// Clears the input field and returns its text.
+    pub fn take_input(&mut self) -> String {
         self.cursor_position = 0;
-        self.messages.push(format!("You: {}", message.clone()));
-        message
+        std::mem::take(&mut self.input)
     }
 
-    pub fn add_ai_response(&mut self, response: String) {
-        self.messages.push(format!("AI: {}", response));
+    /// Stops the reply being generated, keeping the text received so far.
+    pub fn cancel_generation(&mut self) {
+        if let Some(generation) = self.generation.take() {
+            generation.handle.abort();
+            if let Some(reply) = self.messages.last_mut() {
+                reply.cancelled = true;
+            }
+            self.clear_status();
+        }
+    }
+
+    /// Aborts every running background task.
+    pub fn cancel_tasks(&mut self) {
+        for task in [self.generation.take(), self.search.take()]
+            .into_iter()
+            .flatten()
+        {
+            task.handle.abort();
+        }
     }
 
     pub fn next_tab(&mut self) {
@@ -186,7 +694,19 @@ impl App {
     }
 }
 
-pub async fn run_app(llama_service: &LlamaService, vector_db: &VectorDB) -> Result<()> {
+fn is_current(task: &Option<BackgroundTask>, id: u64) -> bool {
+    task.as_ref().is_some_and(|task| task.id == id)
+}
+
+/// Runs the TUI until the user quits. The services are connected in the background, so
+/// that the TUI starts even if they are unreachable; with `watch` set, changed notes are
+/// then re-indexed as well.
+pub async fn run_app(
+    config: AppConfiguration,
+    llama_service: Arc<LlamaService>,
+    vector_db: Arc<VectorDB>,
+    watch: bool,
+) -> Result<()> {
     // Setup terminal
     enable_raw_mode()
         .map_err(|e| AppError::TUIError(format!("Failed to enable raw mode: {}", e)))?;
@@ -201,30 +721,51 @@ pub async fn run_app(llama_service: &LlamaService, vector_db: &VectorDB) -> Resu
 
     // Create app state
     let mut app = App::new();
-    app.messages.push(String::from(
-        "AI: Welcome to Raggy Notes! How can I help you today?",
+    app.settings = SettingsForm::new(&config);
+    app.messages.push(ChatMessage::assistant(
+        "Welcome to Raggy Notes! How can I help you today?",
     ));
-    app.set_status("Connected to Ollama and Qdrant");
-
-    // Use a oneshot channel to handle the status timeout
-    let (status_sender, status_receiver) = tokio::sync::oneshot::channel();
+    app.set_status("Connecting to the LLM provider and the vector store...");
+
+    let (sender, receiver) = unbounded_channel();
+
+    // Connect in the background, then keep the index in sync with the notes
+    let background = {
+        let config = config.clone();
+        let llama_service = llama_service.clone();
+        let vector_db = vector_db.clone();
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            let check = check_connections(&config, &llama_service, &vector_db).await;
+            let connected = check.models.is_ok() && check.store.is_ok();
+            let _ = sender.send(AppMessage::Connected(check));
+
+            if !watch {
+                return;
+            }
+            if !connected {
+                warn!("Not watching the notes, as the services are unavailable");
+                return;
+            }
+            watch_in_background(&config, &llama_service, &vector_db, sender).await;
+        })
+    };
 
-    // Spawn a task to clear the status after 3 seconds
-    tokio::spawn(async move {
-        tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
-        // Send signal to clear status
-        let _ = status_sender.send(());
-    });
+    let mut services = Services {
+        config: Arc::new(config),
+        llama_service,
+        vector_db,
+        messages: sender,
+        watch,
+        background,
+    };
 
     // Run the application
-    let result = tokio::select! {
-        ui_result = run_ui(&mut terminal, &mut app, llama_service, vector_db) => ui_result,
-        _ = status_receiver => {
-            // Clear status message after timeout
-            app.clear_status();
-            run_ui(&mut terminal, &mut app, llama_service, vector_db).await
-        }
-    };
+    let result = run_ui(&mut terminal, &mut app, &mut services, receiver).await;
+
+    // Don't leave requests running in the background after quitting
+    app.cancel_tasks();
+    services.background.abort();
 
     // Restore terminal
     restore_terminal(&mut terminal).map_err(|e| {
@@ -236,6 +777,67 @@ pub async fn run_app(llama_service: &LlamaService, vector_db: &VectorDB) -> Resu
     result
 }
 
+/// Checks that the LLM provider and the vector store are reachable, and prepares the
+/// collection for the embedding model once both are.
+async fn check_connections(
+    config: &AppConfiguration,
+    llama_service: &LlamaService,
+    vector_db: &VectorDB,
+) -> ConnectionCheck {
+    let (models, store) =
+        tokio::join!(llama_service.test_connection(), vector_db.test_connection());
+    let mut check = ConnectionCheck {
+        models: models.map_err(|e| {
+            let problem = match e {
+                AppError::ConfigError(_) => "misconfigured",
+                _ => "unreachable",
+            };
+            ServiceProblem::new(format!("{} {}", config.llm_provider.label(), problem), e)
+        }),
+        store: store.map_err(|e| {
+            ServiceProblem::new(format!("{} unreachable", config.vector_store.label()), e)
+        }),
+    };
+
+    if check.models.is_ok() && check.store.is_ok() {
+        match llama_service.embedding_spec().await {
+            Ok(spec) => {
+                check.store = vector_db
+                    .initialize_collections(&spec)
+                    .await
+                    .map_err(|e| ServiceProblem::new("Index unavailable", e));
+            }
+            Err(e) => {
+                check.models = Err(ServiceProblem::new("Embedding model unavailable", e));
+            }
+        }
+    }
+    check
+}
+
+/// Re-indexes changed notes until the task is aborted, reporting to the UI loop.
+async fn watch_in_background(
+    config: &AppConfiguration,
+    llama_service: &LlamaService,
+    vector_db: &VectorDB,
+    messages: UnboundedSender<AppMessage>,
+) {
+    let (sender, mut watch_events) = unbounded_channel();
+    let forward = async {
+        while let Some(event) = watch_events.recv().await {
+            if messages.send(AppMessage::Watch(event)).is_err() {
+                break;
+            }
+        }
+    };
+    let watch = async {
+        if let Err(e) = watch_notes(config, llama_service, vector_db, Some(sender)).await {
+            error!("Error watching notes: {}", e);
+        }
+    };
+    tokio::join!(watch, forward);
+}
+
 fn restore_terminal<W: std::io::Write>(
     terminal: &mut Terminal<CrosstermBackend<W>>,
 ) -> io::Result<()> {
@@ -249,214 +851,429 @@ fn restore_terminal<W: std::io::Write>(
     Ok(())
 }
 
+/// Redraws the UI and waits for the next terminal event, background message or tick.
 async fn run_ui<B: Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
-    llama_service: &LlamaService,
-    vector_db: &VectorDB,
+    services: &mut Services,
+    mut messages: UnboundedReceiver<AppMessage>,
 ) -> Result<()> {
-    loop {
+    let mut events = EventStream::new();
+    let mut tick = tokio::time::interval(TICK_RATE);
+
+    while !app.should_quit {
         terminal.draw(|f| ui(f, app))?;
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                match key.code {
-                    KeyCode::Char('q') => {
-                        if key
-                            .modifiers
-                            .contains(crossterm::event::KeyModifiers::CONTROL)
-                        {
-                            return Ok(());
-                        } else {
-                            app.insert_char('q');
-                        }
-                    }
-                    KeyCode::Char('c') => {
-                        if key
-                            .modifiers
-                            .contains(crossterm::event::KeyModifiers::CONTROL)
-                        {
-                            return Ok(());
-                        } else {
-                            app.insert_char('c');
-                        }
-                    }
-                    KeyCode::Char(c) => {
-                        app.insert_char(c);
-                    }
-                    KeyCode::Backspace => {
-                        app.delete_char();
-                    }
-                    KeyCode::Left => {
-                        app.move_cursor_left();
-                    }
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(event)) => handle_event(app, services, event),
+                Some(Err(e)) => return Err(e.into()),
+                None => break,
+            },
+            Some(message) = messages.recv() => app.on_message(message),
+            _ = tick.tick() => app.on_tick(),
+        }
 
-                    KeyCode::Up => {
-                        if matches!(app.selected_tab, Tab::Search) {
-                            app.previous_result();
-                        }
-                    }
+        if let Some(request) = app.pending_edit.take() {
+            // Stop reading terminal events, so that the keys go to the editor
+            drop(events);
+            edit_note(terminal, app, services, request).await?;
+            events = EventStream::new();
+        }
 
-                    KeyCode::Down => {
-                        if matches!(app.selected_tab, Tab::Search) {
-                            app.next_result();
-                        }
-                    }
-                    KeyCode::Right => {
-                        app.move_cursor_right();
-                    }
-                    KeyCode::Tab => {
-                        app.next_tab();
-                    }
-                    KeyCode::BackTab => {
-                        app.previous_tab();
-                    }
-                    KeyCode::Enter => {
-                        match app.selected_tab {
-                            Tab::Chat => {
-                                if !app.input.is_empty() {
-                                    let user_message = app.submit_message();
-                                    app.set_status("Thinking...");
-                                    // Redraw UI
-                                    terminal.draw(|f| ui(f, app))?;
-
-                                    // Use RAG-enhanced completion
-                                    match llama_service
-                                        .generate_rag_completion(&user_message, vector_db)
-                                        .await
-                                    {
-                                        Ok(response) => {
-                                            app.add_ai_response(response);
-                                        }
-                                        Err(e) => {
-                                            app.add_ai_response(format!(
-                                                "Error generating response: {}",
-                                                e
-                                            ));
-                                        }
-                                    }
-
-                                    app.clear_status();
-                                }
-                            }
-                            Tab::Search => {
-                                if !app.input.is_empty() {
-                                    let search_query = app.submit_message();
-                                    app.set_status("Searching...");
-                                    app.clear_search_results();
-
-                                    terminal.draw(|f| ui(f, app))?;
-
-                                    // Get embedding for search query
-                                    match llama_service.get_embedding(&search_query).await {
-                                        Ok(embedding) => {
-                                            // Search for similar notes
-                                            match vector_db
-                                                .search_similar_notes(embedding, 10)
-                                                .await
-                                            {
-                                                Ok(results) => {
-                                                    if results.result.is_empty() {
-                                                        app.add_ai_response("No relevant notes found for your query.".into());
-                                                    } else {
-                                                        app.add_ai_response(format!(
-                                                            "Found {} relevant notes.",
-                                                            results.result.len()
-                                                        ));
-
-                                                        // Process search results
-                                                        for point in results.result {
-                                                            let title = point
-                                                                .payload
-                                                                .get("title")
-                                                                .and_then(|v| v.as_str())
-                                                                .map(|v| v.to_string())
-                                                                .unwrap_or_else(|| {
-                                                                    "Untitled".to_string()
-                                                                });
-
-                                                            let content = point
-                                                                .payload
-                                                                .get("content")
-                                                                .and_then(|v| v.as_str())
-                                                                .map(|v| v.to_string())
-                                                                .unwrap_or_else(|| {
-                                                                    "No content".to_string()
-                                                                });
-
-                                                            let file_path = point
-                                                                .payload
-                                                                .get("file_path")
-                                                                .and_then(|v| v.as_str())
-                                                                .map(|v| v.to_string())
-                                                                .unwrap_or_else(|| {
-                                                                    "Unknown path".to_string()
-                                                                });
-
-                                                            let id = match point
-                                                                .id
-                                                                .map(|id| id.point_id_options)
-                                                                .flatten()
-                                                            {
-                                                                None => "Unknown ID".to_string(),
-                                                                Some(id_opt) => match id_opt {
-                                                                    PointIdOptions::Num(num) => {
-                                                                        num.to_string()
-                                                                    }
-                                                                    PointIdOptions::Uuid(uid) => {
-                                                                        uid
-                                                                    }
-                                                                },
-                                                            };
-
-                                                            // Add to search results
-                                                            app.add_search_result(
-                                                                id,
-                                                                title,
-                                                                content,
-                                                                point.score,
-                                                                file_path,
-                                                            );
-                                                        }
-
-                                                        // Select first result by default
-                                                        if !app.search_results.is_empty() {
-                                                            app.selected_result = Some(0);
-                                                        }
-                                                    }
-                                                }
-                                                Err(e) => {
-                                                    app.add_ai_response(format!(
-                                                        "Error searching notes: {}",
-                                                        e
-                                                    ));
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            app.add_ai_response(format!(
-                                                "Error generating embedding: {}",
-                                                e
-                                            ));
-                                        }
-                                    }
-
-                                    app.clear_status();
-                                }
-                            }
-                            Tab::Settings => {
-                                // Handle settings tab actions
-                            }
-                        }
-                    }
+        // Tasks started from now on use the services of the new settings
+        if let Some(connected) = app.pending_services.take() {
+            services.apply(connected);
+            app.settings.reset(&services.config);
+        }
+    }
+
+    Ok(())
+}
+
+/// Suspends the TUI while the user edits a note, then re-indexes the note.
+async fn edit_note<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    services: &Services,
+    request: EditRequest,
+) -> Result<()> {
+    suspend_terminal()
+        .map_err(|e| AppError::TUIError(format!("Failed to suspend terminal: {}", e)))?;
+    let edited = open_in_editor(&request).await;
+    resume_terminal(terminal)
+        .map_err(|e| AppError::TUIError(format!("Failed to resume terminal: {}", e)))?;
+
+    match edited {
+        Ok(()) => start_reindex(app, services, request.path),
+        Err(e) => app.set_status(e.to_string()),
+    }
+    Ok(())
+}
+
+/// Hands the terminal back to the shell, like `restore_terminal`, without dropping it.
+fn suspend_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        Show
+    )
+}
+
+fn resume_terminal<B: Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()
+}
+
+fn handle_event(app: &mut App, services: &Services, event: Event) {
+    // Resizes need no handling besides the redraw after every event
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Press => handle_key(app, services, key),
+        Event::Mouse(mouse) => {
+            if let Some(view) = app.scroll_view() {
+                match mouse.kind {
+                    MouseEventKind::ScrollUp => view.scroll_up(WHEEL_SCROLL_LINES),
+                    MouseEventKind::ScrollDown => view.scroll_down(WHEEL_SCROLL_LINES),
                     _ => {}
                 }
             }
         }
+        _ => {}
+    }
+}
+
+fn handle_key(app: &mut App, services: &Services, key: KeyEvent) {
+    if matches!(app.selected_tab, Tab::Settings) && handle_settings_key(app, services, key) {
+        return;
+    }
+
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Char('c')
+            if key.modifiers.contains(KeyModifiers::CONTROL) =>
+        {
+            app.should_quit = true;
+        }
+        KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if matches!(app.selected_tab, Tab::Search) {
+                app.edit_selected_result();
+            }
+        }
+        KeyCode::Char(c) => {
+            app.insert_char(c);
+        }
+        KeyCode::Backspace => {
+            app.delete_char();
+        }
+        KeyCode::Esc => {
+            app.cancel_generation();
+        }
+        KeyCode::Left => {
+            app.move_cursor_left();
+        }
+        KeyCode::Up => match app.selected_tab {
+            Tab::Chat => app.previous_source(),
+            Tab::Search => app.previous_result(),
+            Tab::Settings => {}
+        },
+        KeyCode::Down => match app.selected_tab {
+            Tab::Chat => app.next_source(),
+            Tab::Search => app.next_result(),
+            Tab::Settings => {}
+        },
+        KeyCode::Right => {
+            app.move_cursor_right();
+        }
+        KeyCode::PageUp => {
+            if let Some(view) = app.scroll_view() {
+                view.page_up();
+            }
+        }
+        KeyCode::PageDown => {
+            if let Some(view) = app.scroll_view() {
+                view.page_down();
+            }
+        }
+        KeyCode::Home => {
+            if let Some(view) = app.scroll_view() {
+                view.scroll_to_top();
+            }
+        }
+        KeyCode::End => {
+            if let Some(view) = app.scroll_view() {
+                view.scroll_to_bottom();
+            }
+        }
+        KeyCode::Tab => {
+            app.next_tab();
+        }
+        KeyCode::BackTab => {
+            app.previous_tab();
+        }
+        KeyCode::Enter => match app.selected_tab {
+            Tab::Chat if app.input.is_empty() => app.open_selected_source(),
+            Tab::Chat => start_chat_reply(app, services),
+            Tab::Search => start_search(app, services),
+            Tab::Settings => {}
+        },
+        _ => {}
     }
 }
 
-fn ui(f: &mut Frame, app: &App) {
+/// Edits the settings form, returning whether the key was handled.
+fn handle_settings_key(app: &mut App, services: &Services, key: KeyEvent) -> bool {
+    let form = &mut app.settings;
+    match key.code {
+        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => form.clear_field(),
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => form.insert_char(c),
+        KeyCode::Backspace => form.delete_char(),
+        KeyCode::Up => form.previous_field(),
+        KeyCode::Down => form.next_field(),
+        KeyCode::Left => form.cycle_model(false),
+        KeyCode::Right => form.cycle_model(true),
+        KeyCode::Esc => {
+            form.reset(&services.config);
+            app.set_timed_status("Discarded the changes", RESULT_STATUS_DURATION);
+        }
+        KeyCode::Enter => start_apply_settings(app, services),
+        _ => return false,
+    }
+    true
+}
+
+/// Validates the settings form, then connects to the services and saves the settings.
+fn start_apply_settings(app: &mut App, services: &Services) {
+    if app.applying_settings {
+        return;
+    }
+
+    let config = match app.settings.to_config(&services.config) {
+        Ok(config) => config,
+        Err(e) => {
+            app.set_status(e.to_string());
+            return;
+        }
+    };
+    let offline = app.llm_problem.is_some() || app.store_problem.is_some();
+    if config == *services.config && !offline {
+        app.settings.modified = false;
+        app.set_timed_status("No changes to save", RESULT_STATUS_DURATION);
+        return;
+    }
+
+    // Save the edits over the configuration file, leaving out the environment overrides
+    let file = match AppConfiguration::load_file() {
+        Ok(file) => file,
+        Err(e) => {
+            app.set_status(format!("Failed to load configuration: {}", e));
+            return;
+        }
+    };
+    let saved = config_to_save(file, &services.config, &config);
+
+    let reconnect_store = config.vector_store != services.config.vector_store
+        || config.qdrant_url != services.config.qdrant_url
+        || app.store_problem.is_some();
+    app.applying_settings = true;
+    app.set_status("Connecting with the new settings...");
+
+    let vector_db = services.vector_db.clone();
+    let messages = services.messages.clone();
+    tokio::spawn(async move {
+        let result = connect_services(config, saved, vector_db, reconnect_store).await;
+        let _ = messages.send(AppMessage::SettingsApplied(result));
+    });
+}
+
+/// Checks that the services are reachable with `config`, and that the index matches its
+/// embedding model, before saving `saved` as the configuration file.
+async fn connect_services(
+    config: AppConfiguration,
+    saved: AppConfiguration,
+    vector_db: Arc<VectorDB>,
+    reconnect_store: bool,
+) -> Result<ConnectedServices> {
+    let llama_service = LlamaService::from_config(&config)?;
+    let models = llama_service.test_connection().await?;
+    let embedding_spec = llama_service.embedding_spec().await?;
+
+    let vector_db = if reconnect_store {
+        let vector_db = VectorDB::from_config(&config)?;
+        vector_db.test_connection().await?;
+        vector_db.initialize_collections(&embedding_spec).await?;
+        Some(Arc::new(vector_db))
+    } else {
+        vector_db.initialize_collections(&embedding_spec).await?;
+        None
+    };
+
+    saved
+        .save()
+        .map_err(|e| AppError::ConfigError(format!("Failed to save configuration: {}", e)))?;
+
+    Ok(ConnectedServices {
+        config,
+        llama_service: Arc::new(llama_service),
+        vector_db,
+        models,
+    })
+}
+
+/// Sends the chat input to the LLM and streams the reply into a new message.
+fn start_chat_reply(app: &mut App, services: &Services) {
+    if app.input.is_empty() || app.generation.is_some() {
+        return;
+    }
+
+    if let Some(problem) = app.chat_problem() {
+        app.set_timed_status(problem.to_string(), RESULT_STATUS_DURATION);
+        return;
+    }
+
+    let (question, filter) = parse_query(&app.input);
+    if question.is_empty() {
+        app.set_timed_status(
+            "Please add a question to the filters.",
+            RESULT_STATUS_DURATION,
+        );
+        return;
+    }
+
+    let history = app.messages.clone();
+    let user_message = app.take_input();
+    app.messages.push(ChatMessage::user(user_message));
+
+    app.set_status(with_filter("Thinking... (Esc to cancel)", &filter));
+    app.messages.push(ChatMessage::assistant(String::new()));
+
+    let id = app.next_task_id();
+    let llama_service = services.llama_service.clone();
+    let vector_db = services.vector_db.clone();
+    let messages = services.messages.clone();
+
+    let handle = tokio::spawn(async move {
+        let (tokens, mut token_receiver) = unbounded_channel();
+
+        // Use RAG-enhanced completion, forwarding its tokens as they arrive
+        let generate = llama_service
+            .generate_rag_completion_stream(&history, &question, &vector_db, &filter, tokens);
+        let forward = async {
+            while let Some(token) = token_receiver.recv().await {
+                let _ = messages.send(AppMessage::Token { id, token });
+            }
+        };
+        let (result, ()) = tokio::join!(generate, forward);
+
+        let _ = messages.send(AppMessage::ReplyFinished { id, result });
+    });
+
+    app.generation = Some(BackgroundTask { id, handle });
+}
+
+/// Re-indexes a note after it was edited, so that search reflects the changes.
+fn start_reindex(app: &mut App, services: &Services, path: PathBuf) {
+    if let Some(problem) = app.chat_problem() {
+        app.set_timed_status(
+            format!("{} not re-indexed. {}", path.display(), problem),
+            RESULT_STATUS_DURATION,
+        );
+        return;
+    }
+    app.set_status(format!("Re-indexing {}...", path.display()));
+
+    let config = services.config.clone();
+    let llama_service = services.llama_service.clone();
+    let vector_db = services.vector_db.clone();
+    let messages = services.messages.clone();
+
+    tokio::spawn(async move {
+        let result = async {
+            let indexed_hash = vector_db.indexed_note_hash(&path.to_string_lossy()).await?;
+            let outcome = index_markdown_file(
+                &path,
+                indexed_hash.as_deref(),
+                false,
+                &config,
+                &llama_service,
+                &vector_db,
+            )
+            .await?;
+            vector_db.flush().await?;
+            Ok(outcome)
+        }
+        .await;
+
+        let _ = messages.send(AppMessage::Reindexed { path, result });
+    });
+}
+
+/// Searches the notes for the input, replacing a search that is still running.
+fn start_search(app: &mut App, services: &Services) {
+    if app.input.is_empty() {
+        return;
+    }
+
+    let (search_query, filter) = parse_query(&app.input);
+    if search_query.is_empty() {
+        app.set_timed_status(
+            "Please add search terms to the filters.",
+            RESULT_STATUS_DURATION,
+        );
+        return;
+    }
+    if let Some(problem) = &app.store_problem {
+        app.set_timed_status(problem.to_string(), RESULT_STATUS_DURATION);
+        return;
+    }
+    app.take_input();
+
+    if let Some(search) = app.search.take() {
+        search.handle.abort();
+    }
+    app.set_status(with_filter("Searching...", &filter));
+    app.clear_search_results();
+
+    let id = app.next_task_id();
+    let llama_service = services.llama_service.clone();
+    let vector_db = services.vector_db.clone();
+    let messages = services.messages.clone();
+    let keywords_only = app.llm_problem.is_some();
+
+    let handle = tokio::spawn(async move {
+        let result = async {
+            // Without the LLM provider the query can't be embedded
+            if keywords_only {
+                return vector_db
+                    .search_keywords(&search_query, SEARCH_RESULT_LIMIT, &filter)
+                    .await;
+            }
+
+            // Get embedding for search query, then search notes by meaning and keywords
+            let embedding = llama_service.get_embedding(&search_query).await?;
+            vector_db
+                .search_notes(&search_query, embedding, SEARCH_RESULT_LIMIT, &filter)
+                .await
+        }
+        .await;
+
+        let _ = messages.send(AppMessage::SearchFinished { id, result });
+    });
+
+    app.search = Some(BackgroundTask { id, handle });
+}
+
+/// Appends the active filters to a status message.
+fn with_filter(status: &str, filter: &SearchFilter) -> String {
+    if filter.is_empty() {
+        status.to_string()
+    } else {
+        format!("{} [{}]", status, filter)
+    }
+}
+
+fn ui(f: &mut Frame, app: &mut App) {
     // Create main layout
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -469,7 +1286,7 @@ fn ui(f: &mut Frame, app: &App) {
             ]
             .as_ref(),
         )
-        .split(f.size());
+        .split(f.area());
 
     // Render tab bar
     let tabs = ["Chat", "Search", "Settings"];
@@ -501,23 +1318,81 @@ fn ui(f: &mut Frame, app: &App) {
     let tabs_paragraph = Paragraph::new(tab_items);
     f.render_widget(tabs_paragraph, chunks[0]);
 
+    // Keep unreachable services in view while other messages use the status bar
+    let problems: Vec<&str> = [&app.llm_problem, &app.store_problem]
+        .into_iter()
+        .flatten()
+        .map(|problem| problem.summary.as_str())
+        .collect();
+    if !problems.is_empty() {
+        let problems_paragraph = Paragraph::new(problems.join(" | "))
+            .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            .alignment(ratatui::layout::Alignment::Right);
+        f.render_widget(problems_paragraph, chunks[0]);
+    }
+
     // Render content based on selected tab
     match app.selected_tab {
         Tab::Chat => {
             // Render chat messages
-            let messages: Vec<ListItem> = app
-                .messages
-                .iter()
-                .map(|m| {
-                    let message = Text::from(m.clone());
-                    ListItem::new(message)
-                })
-                .collect();
+            let mut source_index = 0;
+            let mut lines: Vec<Line> = Vec::new();
+            for m in &app.messages {
+                let mut message = match m.role {
+                    Role::User => Text::from(format!("You: {}", m.content)),
+                    Role::Assistant | Role::System => {
+                        let mut reply = render_markdown(&m.content);
+                        match reply.lines.first_mut() {
+                            Some(first_line) => first_line.spans.insert(0, Span::raw("AI: ")),
+                            None => reply.lines.push(Line::from("AI: ")),
+                        }
+                        reply
+                    }
+                };
 
-            let messages_list =
-                List::new(messages).block(Block::default().borders(Borders::ALL).title("Chat"));
+                if m.cancelled {
+                    let marker = Span::styled(" [cancelled]", Style::default().fg(Color::DarkGray));
+                    match message.lines.last_mut() {
+                        Some(last_line) => last_line.spans.push(marker),
+                        None => message.push_line(Line::from(marker)),
+                    }
+                }
 
-            f.render_widget(messages_list, chunks[1]);
+                // List the cited sources under the answer, numbered as the model cites them
+                for (number, source) in cited_sources(&m.content, &m.sources) {
+                    let style = if app.selected_source == Some(source_index) {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default().fg(Color::DarkGray)
+                    };
+                    message.push_line(Line::styled(
+                        format!(
+                            "  [{}] {} - {} ({:.2})",
+                            number, source.section, source.file_path, source.score
+                        ),
+                        style,
+                    ));
+                    source_index += 1;
+                }
+
+                if !lines.is_empty() {
+                    lines.push(Line::default());
+                }
+                lines.extend(message.lines);
+            }
+
+            // Wrap long lines and scroll to the part of the conversation being read
+            let area = chunks[1];
+            let messages = Paragraph::new(lines).wrap(Wrap { trim: false });
+            app.chat_scroll.update(
+                messages.line_count(area.width.saturating_sub(2)),
+                area.height.saturating_sub(2) as usize,
+            );
+            let messages = messages
+                .scroll((app.chat_scroll.offset(), 0))
+                .block(Block::default().borders(Borders::ALL).title("Chat"));
+
+            f.render_widget(messages, area);
         }
         Tab::Search => {
             // Create a split layout for search results and preview
@@ -550,11 +1425,15 @@ fn ui(f: &mut Frame, app: &App) {
 
                         let score_text = format!(" ({:.2})", result.score);
                         let spans = vec![
-                            Span::styled(&result.title, style.add_modifier(Modifier::BOLD)),
+                            Span::styled(&result.section, style.add_modifier(Modifier::BOLD)),
                             Span::styled(score_text, style.fg(Color::DarkGray)),
                         ];
+                        let preview = Span::styled(
+                            result.content_preview.replace('\n', " "),
+                            style.fg(Color::DarkGray),
+                        );
 
-                        ListItem::new(Line::from(spans))
+                        ListItem::new(vec![Line::from(spans), Line::from(preview)])
                     })
                     .collect()
             };
@@ -577,20 +1456,32 @@ fn ui(f: &mut Frame, app: &App) {
                         Span::styled("Title: ", Style::default().add_modifier(Modifier::BOLD)),
                         Span::raw(&selected_result.title),
                     ]));
+                    content_lines.push(Line::from(vec![
+                        Span::styled("Section: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::raw(&selected_result.section),
+                    ]));
                     content_lines.push(Line::from(vec![
                         Span::styled("File: ", Style::default().add_modifier(Modifier::BOLD)),
                         Span::raw(&selected_result.file_path),
                     ]));
+                    if !selected_result.tags.is_empty() {
+                        content_lines.push(Line::from(vec![
+                            Span::styled("Tags: ", Style::default().add_modifier(Modifier::BOLD)),
+                            Span::raw(selected_result.tags.join(", ")),
+                        ]));
+                    }
                     content_lines.push(Line::from(vec![
                         Span::styled("Score: ", Style::default().add_modifier(Modifier::BOLD)),
                         Span::raw(format!("{:.2}", selected_result.score)),
                     ]));
+                    content_lines.push(Line::from(vec![
+                        Span::styled("ID: ", Style::default().add_modifier(Modifier::BOLD)),
+                        Span::styled(&selected_result.id, Style::default().fg(Color::DarkGray)),
+                    ]));
                     content_lines.push(Line::from(Span::raw(""))); // Empty line
 
-                    // Add full content with line breaks preserved
-                    for content_line in selected_result.content.lines() {
-                        content_lines.push(Line::from(Span::raw(content_line)));
-                    }
+                    // Add the full content, rendered as markdown
+                    content_lines.extend(render_markdown(&selected_result.content).lines);
 
                     content_lines
                 } else {
@@ -600,16 +1491,61 @@ fn ui(f: &mut Frame, app: &App) {
                 vec![Line::from("Select a result to see preview")]
             };
 
-            let preview = Paragraph::new(preview_content)
-                .block(Block::default().borders(Borders::ALL).title("Preview"))
-                .wrap(ratatui::widgets::Wrap { trim: false });
+            let area = search_chunks[1];
+            let preview = Paragraph::new(preview_content).wrap(Wrap { trim: false });
+            app.preview_scroll.update(
+                preview.line_count(area.width.saturating_sub(2)),
+                area.height.saturating_sub(2) as usize,
+            );
+            let preview = preview
+                .scroll((app.preview_scroll.offset(), 0))
+                .block(Block::default().borders(Borders::ALL).title("Preview"));
 
-            f.render_widget(preview, search_chunks[1]);
+            f.render_widget(preview, area);
         }
         Tab::Settings => {
-            // Render settings
-            let settings = Paragraph::new("Settings (not yet implemented)")
-                .block(Block::default().borders(Borders::ALL).title("Settings"));
+            let form = &app.settings;
+            let label_style = Style::default().add_modifier(Modifier::BOLD);
+            let hint_style = Style::default().fg(Color::DarkGray);
+
+            let mut lines = Vec::new();
+            for (i, field) in SETTINGS_FIELDS.iter().enumerate() {
+                let selected = i == form.selected;
+                // Show the line breaks of the prompt template without breaking the form
+                let value = form.value(*field).replace('\n', " ⏎ ");
+
+                let mut spans = vec![Span::styled(format!("{:<17}", field.label()), label_style)];
+                if value.is_empty() && *field == SettingsField::LlmBaseUrl {
+                    spans.push(Span::styled("(provider default)", hint_style));
+                } else {
+                    spans.push(Span::raw(value));
+                }
+                if selected {
+                    spans = spans
+                        .into_iter()
+                        .map(|span| span.patch_style(Style::default().fg(Color::Yellow)))
+                        .collect();
+                    spans[0] = spans[0]
+                        .clone()
+                        .patch_style(Style::default().add_modifier(Modifier::REVERSED));
+                    if field.is_model() && !form.models.is_empty() {
+                        spans.push(Span::styled(
+                            format!("  ←/→ {} models", form.models.len()),
+                            hint_style,
+                        ));
+                    }
+                }
+                lines.push(Line::from(spans));
+            }
+
+            let title = if form.modified {
+                "Settings (modified, Enter to save)"
+            } else {
+                "Settings"
+            };
+            let settings = Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title(title));
 
             f.render_widget(settings, chunks[1]);
         }
@@ -621,26 +1557,38 @@ fn ui(f: &mut Frame, app: &App) {
         .block(Block::default().borders(Borders::ALL).title("Input"));
 
     f.render_widget(input, chunks[2]);
-    f.set_cursor(
-        chunks[2].x + 1 + app.cursor_position as u16,
-        chunks[2].y + 1,
-    );
+    // The Settings tab edits the selected field instead of the input
+    if !matches!(app.selected_tab, Tab::Settings) {
+        f.set_cursor_position((
+            chunks[2].x + 1 + app.cursor_position as u16,
+            chunks[2].y + 1,
+        ));
+    }
 
     // Render status message if present
     if let Some(status) = &app.status_message {
         let status_style = Style::default().fg(Color::White).bg(Color::Blue);
-        let status_widget = Paragraph::new(status.as_str())
+        let status = if app.is_busy() {
+            format!("{} {}", SPINNER_FRAMES[app.spinner_frame], status)
+        } else {
+            status.clone()
+        };
+        let status_widget = Paragraph::new(status)
             .style(status_style)
             .alignment(ratatui::layout::Alignment::Center);
         f.render_widget(status_widget, chunks[3]);
     } else {
         // Render help text
         let help_text = match app.selected_tab {
-            Tab::Chat => "Ctrl+Q/Ctrl+C: Quit | Tab: Switch tabs | Enter: Send message",
+            Tab::Chat => {
+                "Ctrl+Q/Ctrl+C: Quit | Tab: Switch tabs | Enter: Send message | Esc: Stop answer | ↑/↓: Select source, Enter: Open it | PgUp/PgDn/Home/End: Scroll | Filters: tag:x path:dir/ after:/before:YYYY-MM-DD"
+            }
             Tab::Search => {
-                "Ctrl+Q/Ctrl+C: Quit | Tab: Switch tabs | Enter: Search | ↑/↓: Navigate results"
+                "Ctrl+Q/Ctrl+C: Quit | Tab: Switch tabs | Enter: Search | ↑/↓: Navigate results | PgUp/PgDn: Scroll preview | Ctrl+E: Edit note | Filters: tag:x path:dir/ after:/before:YYYY-MM-DD"
+            }
+            Tab::Settings => {
+                "Ctrl+Q/Ctrl+C: Quit | Tab: Switch tabs | ↑/↓: Select setting | Type to edit, Ctrl+U: Clear | ←/→: Pick model | Enter: Save and apply | Esc: Discard changes"
             }
-            Tab::Settings => "Ctrl+Q/Ctrl+C: Quit | Tab: Switch tabs",
         };
 
         let help_style = Style::default().fg(Color::DarkGray);
@@ -648,3 +1596,123 @@ fn ui(f: &mut Frame, app: &App) {
         f.render_widget(help_widget, chunks[3]);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_messages_of_replaced_tasks_are_ignored() {
+        let mut app = App::new();
+        app.messages.push(ChatMessage::assistant(String::new()));
+        app.generation = Some(BackgroundTask {
+            id: 2,
+            handle: tokio::spawn(async {}),
+        });
+
+        app.on_message(AppMessage::Token {
+            id: 1,
+            token: "stale".to_string(),
+        });
+        app.on_message(AppMessage::Token {
+            id: 2,
+            token: "Hello".to_string(),
+        });
+        app.on_message(AppMessage::ReplyFinished {
+            id: 1,
+            result: Ok(RagAnswer {
+                answer: "stale".to_string(),
+                sources: Vec::new(),
+                dropped: Vec::new(),
+            }),
+        });
+        assert!(app.generation.is_some());
+
+        app.on_message(AppMessage::ReplyFinished {
+            id: 2,
+            result: Ok(RagAnswer {
+                answer: "Hello".to_string(),
+                sources: Vec::new(),
+                dropped: Vec::new(),
+            }),
+        });
+        assert!(app.generation.is_none());
+        assert_eq!(app.messages.last().unwrap().content, "Hello");
+    }
+
+    #[test]
+    fn test_unreachable_services_are_reported() {
+        let mut app = App::new();
+        app.on_message(AppMessage::Connected(ConnectionCheck {
+            models: Err(ServiceProblem::new(
+                "Ollama unreachable",
+                "connection refused",
+            )),
+            store: Ok(()),
+        }));
+
+        assert_eq!(
+            app.chat_problem().map(ToString::to_string).as_deref(),
+            Some("Ollama unreachable: connection refused")
+        );
+        assert!(app.store_problem.is_none());
+        assert!(app
+            .status_message
+            .as_deref()
+            .is_some_and(|status| status.starts_with("Ollama unreachable")));
+    }
+
+    #[test]
+    fn test_sources_are_selected_across_messages() {
+        let source = |id: &str| NoteSource {
+            id: id.to_string(),
+            title: "Note".to_string(),
+            section: "Note".to_string(),
+            file_path: format!("/notes/{}.md", id),
+            score: 0.5,
+            similarity: None,
+            snippet: String::new(),
+            byte_start: 0,
+            byte_end: 0,
+        };
+
+        let mut app = App::new();
+        for ids in [vec!["a", "b"], vec!["c"]] {
+            let mut answer = ChatMessage::assistant("answer");
+            answer.sources = ids.into_iter().map(source).collect();
+            app.messages.push(answer);
+        }
+
+        app.previous_source();
+        assert_eq!(app.selected_source, Some(2));
+        app.next_source();
+        assert_eq!(app.selected_source, Some(2));
+
+        app.selected_source = Some(1);
+        app.open_selected_source();
+        assert!(matches!(app.selected_tab, Tab::Search));
+        assert_eq!(app.search_results[0].file_path, "/notes/b.md");
+
+        // Only the cited sources are listed, as by `ask`
+        app.messages[0].content = "Restart the gateway [2].".to_string();
+        app.selected_source = Some(0);
+        app.open_selected_source();
+        assert_eq!(app.search_results[0].file_path, "/notes/b.md");
+        assert_eq!(app.sources().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_reply_keeps_its_text() {
+        let mut app = App::new();
+        app.messages.push(ChatMessage::assistant("Restart the"));
+        app.generation = Some(BackgroundTask {
+            id: 1,
+            handle: tokio::spawn(async {}),
+        });
+
+        app.cancel_generation();
+        assert!(app.generation.is_none());
+        assert_eq!(app.messages[0].content, "Restart the");
+        assert!(app.messages[0].cancelled);
+    }
+}
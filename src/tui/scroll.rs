@@ -0,0 +1,120 @@
+// src/tui/scroll.rs
+
+/// Lines scrolled per mouse wheel step
+pub const WHEEL_SCROLL_LINES: usize = 3;
+
+/// Vertical scroll position of a pane whose content may be taller than the pane.
+///
+/// The content size is only known while rendering, so `update` is called from the UI
+/// with the wrapped line count and the viewport height of the last frame.
+#[derive(Debug, Default)]
+pub struct ScrollView {
+    offset: usize,
+    /// Whether to stay at the bottom as content is added
+    follow: bool,
+    content_height: usize,
+    viewport_height: usize,
+}
+
+impl ScrollView {
+    /// A pane that starts at the bottom and follows new content, like a chat.
+    pub fn following() -> Self {
+        Self {
+            follow: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn offset(&self) -> u16 {
+        self.offset.min(u16::MAX as usize) as u16
+    }
+
+    fn max_offset(&self) -> usize {
+        self.content_height.saturating_sub(self.viewport_height)
+    }
+
+    /// Records the size of the rendered content and keeps the offset within it.
+    pub fn update(&mut self, content_height: usize, viewport_height: usize) {
+        self.content_height = content_height;
+        self.viewport_height = viewport_height;
+        self.offset = if self.follow {
+            self.max_offset()
+        } else {
+            self.offset.min(self.max_offset())
+        };
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.offset = self.offset.min(self.max_offset()).saturating_sub(lines);
+        self.follow = false;
+    }
+
+    /// Scrolls down, following new content again once the bottom is reached.
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.offset = (self.offset + lines).min(self.max_offset());
+        self.follow = self.offset == self.max_offset();
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.page_size());
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.page_size());
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.offset = 0;
+        self.follow = false;
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.offset = self.max_offset();
+        self.follow = true;
+    }
+
+    /// Keeps one line of the previous page visible for orientation.
+    fn page_size(&self) -> usize {
+        self.viewport_height.saturating_sub(1).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_following_view_sticks_to_bottom_until_scrolled_up() {
+        let mut view = ScrollView::following();
+        view.update(50, 10);
+        assert_eq!(view.offset(), 40);
+
+        view.update(60, 10);
+        assert_eq!(view.offset(), 50);
+
+        view.page_up();
+        assert_eq!(view.offset(), 41);
+        view.update(70, 10);
+        assert_eq!(view.offset(), 41);
+
+        view.scroll_down(100);
+        view.update(80, 10);
+        assert_eq!(view.offset(), 70);
+    }
+
+    #[test]
+    fn test_view_is_clamped_to_content() {
+        let mut view = ScrollView::default();
+        view.update(5, 10);
+        view.page_down();
+        assert_eq!(view.offset(), 0);
+
+        view.update(30, 10);
+        view.scroll_to_bottom();
+        assert_eq!(view.offset(), 20);
+        view.update(15, 10);
+        assert_eq!(view.offset(), 5);
+        view.scroll_to_top();
+        assert_eq!(view.offset(), 0);
+    }
+}
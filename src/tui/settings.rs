@@ -0,0 +1,340 @@
+// src/tui/settings.rs
+use std::path::Path;
+
+use crate::config::app_config::{AppConfiguration, PROMPT_NOTES_PLACEHOLDER};
+use crate::prelude::*;
+
+/// Upper bound of the retrieval top-k, to keep prompts within reason
+const MAX_TOP_K: u64 = 50;
+
+/// Editable configuration values, in the order they are shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingsField {
+    ScanPath,
+    LlmBaseUrl,
+    QdrantUrl,
+    CompletionModel,
+    EmbeddingModel,
+    TopK,
+    PromptTemplate,
+}
+
+pub const SETTINGS_FIELDS: [SettingsField; 7] = [
+    SettingsField::ScanPath,
+    SettingsField::LlmBaseUrl,
+    SettingsField::QdrantUrl,
+    SettingsField::CompletionModel,
+    SettingsField::EmbeddingModel,
+    SettingsField::TopK,
+    SettingsField::PromptTemplate,
+];
+
+impl SettingsField {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingsField::ScanPath => "Notes directory",
+            SettingsField::LlmBaseUrl => "LLM endpoint",
+            SettingsField::QdrantUrl => "Qdrant endpoint",
+            SettingsField::CompletionModel => "Chat model",
+            SettingsField::EmbeddingModel => "Embedding model",
+            SettingsField::TopK => "Retrieved notes",
+            SettingsField::PromptTemplate => "Prompt template",
+        }
+    }
+
+    /// Whether the value is picked from the models of the LLM provider
+    pub fn is_model(&self) -> bool {
+        matches!(
+            self,
+            SettingsField::CompletionModel | SettingsField::EmbeddingModel
+        )
+    }
+
+    fn value(&self, config: &AppConfiguration) -> String {
+        match self {
+            SettingsField::ScanPath => config.scan_path.clone(),
+            SettingsField::LlmBaseUrl => config.llm_base_url.clone().unwrap_or_default(),
+            SettingsField::QdrantUrl => config.qdrant_url.clone(),
+            SettingsField::CompletionModel => config.completion_model.clone(),
+            SettingsField::EmbeddingModel => config.embedding_model.clone(),
+            SettingsField::TopK => config.top_k.to_string(),
+            SettingsField::PromptTemplate => config.prompt_template.clone(),
+        }
+    }
+
+    fn copy_value(&self, from: &AppConfiguration, to: &mut AppConfiguration) {
+        match self {
+            SettingsField::ScanPath => to.scan_path = from.scan_path.clone(),
+            SettingsField::LlmBaseUrl => to.llm_base_url = from.llm_base_url.clone(),
+            SettingsField::QdrantUrl => to.qdrant_url = from.qdrant_url.clone(),
+            SettingsField::CompletionModel => to.completion_model = from.completion_model.clone(),
+            SettingsField::EmbeddingModel => to.embedding_model = from.embedding_model.clone(),
+            SettingsField::TopK => to.top_k = from.top_k,
+            SettingsField::PromptTemplate => to.prompt_template = from.prompt_template.clone(),
+        }
+    }
+}
+
+/// Returns the configuration to save once `current` has been edited into `edited`: the
+/// configuration file with only the edited values replaced, so that values set by the
+/// environment are not written to it. Without a file, all the form values are saved.
+pub fn config_to_save(
+    file: Option<AppConfiguration>,
+    current: &AppConfiguration,
+    edited: &AppConfiguration,
+) -> AppConfiguration {
+    let from_file = file.is_some();
+    let mut config = file.unwrap_or_default();
+    for field in SETTINGS_FIELDS {
+        if !from_file || field.value(current) != field.value(edited) {
+            field.copy_value(edited, &mut config);
+        }
+    }
+    config
+}
+
+/// The Settings tab form, holding the edited values until they are applied.
+#[derive(Debug, Default)]
+pub struct SettingsForm {
+    /// Values of `SETTINGS_FIELDS`, in the same order
+    values: Vec<String>,
+    pub selected: usize,
+    /// Models offered by the LLM provider
+    pub models: Vec<String>,
+    /// Whether the values differ from the applied configuration
+    pub modified: bool,
+}
+
+impl SettingsForm {
+    pub fn new(config: &AppConfiguration) -> Self {
+        let mut form = Self::default();
+        form.reset(config);
+        form
+    }
+
+    /// Discards the edits and shows the values of `config`.
+    pub fn reset(&mut self, config: &AppConfiguration) {
+        self.values = SETTINGS_FIELDS
+            .iter()
+            .map(|field| field.value(config))
+            .collect();
+        self.modified = false;
+    }
+
+    pub fn value(&self, field: SettingsField) -> &str {
+        SETTINGS_FIELDS
+            .iter()
+            .position(|f| *f == field)
+            .and_then(|idx| self.values.get(idx))
+            .map_or("", String::as_str)
+    }
+
+    pub fn selected_field(&self) -> SettingsField {
+        SETTINGS_FIELDS[self.selected]
+    }
+
+    fn selected_value(&mut self) -> &mut String {
+        self.modified = true;
+        &mut self.values[self.selected]
+    }
+
+    pub fn next_field(&mut self) {
+        self.selected = (self.selected + 1).min(SETTINGS_FIELDS.len() - 1);
+    }
+
+    pub fn previous_field(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.selected_value().push(c);
+    }
+
+    pub fn delete_char(&mut self) {
+        self.selected_value().pop();
+    }
+
+    pub fn clear_field(&mut self) {
+        self.selected_value().clear();
+    }
+
+    /// Replaces a model field with the next or previous model of the provider.
+    pub fn cycle_model(&mut self, forward: bool) {
+        if !self.selected_field().is_model() || self.models.is_empty() {
+            return;
+        }
+
+        let count = self.models.len();
+        let current = self
+            .models
+            .iter()
+            .position(|m| *m == self.values[self.selected]);
+        let next = match (current, forward) {
+            (Some(idx), true) => (idx + 1) % count,
+            (Some(idx), false) => (idx + count - 1) % count,
+            (None, true) => 0,
+            (None, false) => count - 1,
+        };
+        *self.selected_value() = self.models[next].clone();
+    }
+
+    /// Validates the edited values and returns `current` updated with them.
+    pub fn to_config(&self, current: &AppConfiguration) -> Result<AppConfiguration> {
+        let invalid = |message: String| AppError::ConfigError(message);
+        let mut config = current.clone();
+
+        let scan_path = self.value(SettingsField::ScanPath).trim();
+        if !Path::new(scan_path).is_dir() {
+            return Err(invalid(format!(
+                "Notes directory '{}' does not exist",
+                scan_path
+            )));
+        }
+        config.scan_path = scan_path.to_string();
+
+        let llm_base_url = self.value(SettingsField::LlmBaseUrl).trim();
+        config.llm_base_url = if llm_base_url.is_empty() {
+            None
+        } else {
+            Some(validate_url(SettingsField::LlmBaseUrl, llm_base_url)?)
+        };
+        config.qdrant_url = validate_url(
+            SettingsField::QdrantUrl,
+            self.value(SettingsField::QdrantUrl).trim(),
+        )?;
+
+        for field in [
+            SettingsField::CompletionModel,
+            SettingsField::EmbeddingModel,
+        ] {
+            let model = self.value(field).trim();
+            if model.is_empty() {
+                return Err(invalid(format!("{} must not be empty", field.label())));
+            }
+            match field {
+                SettingsField::CompletionModel => config.completion_model = model.to_string(),
+                _ => config.embedding_model = model.to_string(),
+            }
+        }
+
+        config.top_k = self
+            .value(SettingsField::TopK)
+            .trim()
+            .parse()
+            .ok()
+            .filter(|top_k| (1..=MAX_TOP_K).contains(top_k))
+            .ok_or_else(|| {
+                invalid(format!(
+                    "{} must be a number from 1 to {}",
+                    SettingsField::TopK.label(),
+                    MAX_TOP_K
+                ))
+            })?;
+
+        let prompt_template = self.value(SettingsField::PromptTemplate);
+        if !prompt_template.contains(PROMPT_NOTES_PLACEHOLDER) {
+            return Err(invalid(format!(
+                "{} must contain {} where the notes go",
+                SettingsField::PromptTemplate.label(),
+                PROMPT_NOTES_PLACEHOLDER
+            )));
+        }
+        config.prompt_template = prompt_template.to_string();
+
+        Ok(config)
+    }
+}
+
+fn validate_url(field: SettingsField, url: &str) -> Result<String> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(url.trim_end_matches('/').to_string())
+    } else {
+        Err(AppError::ConfigError(format!(
+            "{} must be an http:// or https:// URL",
+            field.label()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn select(form: &mut SettingsForm, field: SettingsField) {
+        form.selected = SETTINGS_FIELDS.iter().position(|f| *f == field).unwrap();
+    }
+
+    #[test]
+    fn test_form_validates_and_updates_config() {
+        let dir = TempDir::new("notes").unwrap();
+        let current = AppConfiguration::new(dir.path().to_str().unwrap());
+        let mut form = SettingsForm::new(&current);
+
+        select(&mut form, SettingsField::QdrantUrl);
+        form.clear_field();
+        "http://qdrant:6334/"
+            .chars()
+            .for_each(|c| form.insert_char(c));
+        select(&mut form, SettingsField::TopK);
+        form.insert_char('0');
+
+        let config = form.to_config(&current).unwrap();
+        assert_eq!(config.qdrant_url, "http://qdrant:6334");
+        assert_eq!(config.top_k, 50);
+        assert_eq!(config.llm_base_url, None);
+
+        form.insert_char('0');
+        assert!(form.to_config(&current).is_err());
+
+        form.reset(&current);
+        select(&mut form, SettingsField::PromptTemplate);
+        form.clear_field();
+        "Answer briefly".chars().for_each(|c| form.insert_char(c));
+        assert!(form.to_config(&current).is_err());
+    }
+
+    #[test]
+    fn test_config_to_save_keeps_file_values() {
+        let mut file = AppConfiguration::new("/notes");
+        file.llm_base_url = Some("http://localhost:11434".to_string());
+        // As overridden by RAGGY_LLM_URL and RAGGY_COLLECTION
+        let mut current = file.clone();
+        current.llm_base_url = Some("http://gpu-box:11434".to_string());
+        current.collection_name = "work_notes".to_string();
+        let mut edited = current.clone();
+        edited.top_k = 8;
+
+        let saved = config_to_save(Some(file.clone()), &current, &edited);
+        assert_eq!(saved.top_k, 8);
+        assert_eq!(saved.llm_base_url, file.llm_base_url);
+        assert_eq!(saved.collection_name, file.collection_name);
+
+        let saved = config_to_save(None, &current, &edited);
+        assert_eq!(saved.scan_path, "/notes");
+        assert_eq!(saved.llm_base_url, current.llm_base_url);
+        assert_eq!(
+            saved.collection_name,
+            AppConfiguration::default().collection_name
+        );
+    }
+
+    #[test]
+    fn test_cycle_model() {
+        let mut form = SettingsForm::new(&AppConfiguration::new("/tmp"));
+        form.models = vec!["gemma3:4b".to_string(), "llama3".to_string()];
+
+        select(&mut form, SettingsField::TopK);
+        form.cycle_model(true);
+        assert!(!form.modified);
+
+        select(&mut form, SettingsField::CompletionModel);
+        form.cycle_model(true);
+        assert_eq!(form.value(SettingsField::CompletionModel), "llama3");
+        form.cycle_model(true);
+        assert_eq!(form.value(SettingsField::CompletionModel), "gemma3:4b");
+        form.cycle_model(false);
+        assert_eq!(form.value(SettingsField::CompletionModel), "llama3");
+    }
+}